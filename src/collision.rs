@@ -1,5 +1,6 @@
 pub use thin_engine::prelude::*;
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ColliderType { Cylinder, Cube, Sphere }
 impl ColliderType {
     pub fn is_inside(self, point: Vec3) -> bool {
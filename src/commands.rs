@@ -0,0 +1,147 @@
+use std::{path::{Path, PathBuf}, str::FromStr, fmt::Display};
+use serde::{Serialize, de::DeserializeOwned};
+use crate::{or_exit, file_types::{*, scenes::{GameScenes, SCENES_PATH}, script::Script}};
+
+/// loads everything scenes and scripts can refer to, the same way the game does
+fn load_catalogues() {
+    or_exit(stats::STATS_PATH, stats::load_stat_schema(stats::STATS_PATH));
+    or_exit(items::ITEMS_PATH, items::load_items(items::ITEMS_PATH));
+    or_exit(enemies::ENEMIES_PATH, enemies::load_enemies(enemies::ENEMIES_PATH));
+}
+/// every file with one of `extensions` in the current directory
+pub fn files_with(extensions: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(".") else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| PathBuf::from(e.file_name())))
+        .filter(|p| p.extension().is_some_and(|e| extensions.iter().any(|x| e == *x)))
+        .collect();
+    files.sort();
+    files
+}
+/// `fmt [--check] [files..]` rewrites scenes and scripts in their canonical form,
+/// every `.scn` and `.scr` in the current directory if no files are given.
+/// with `--check` nothing is written and the files that would change are listed instead.
+/// gives the exit code
+pub fn fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|a| a == "--check");
+    let mut paths: Vec<PathBuf> = args.iter().filter(|a| *a != "--check").map(PathBuf::from).collect();
+    if paths.is_empty() { paths = files_with(&["scn", "scr"]) }
+    load_catalogues();
+
+    let mut code = 0;
+    for path in paths {
+        let name = path.display().to_string();
+        match fmt_file(&path) {
+            Ok(None) => (),
+            Ok(Some(_)) if check => { println!("{name} is not formatted"); code = 1 },
+            Ok(Some(formatted)) => if let Err(e) = std::fs::write(&path, formatted) {
+                eprintln!("could not write {name}, {e}");
+                code = 1;
+            },
+            Err(message) => { eprint!("{message}"); code = 1 },
+        }
+    }
+    code
+}
+/// the file in its canonical form, or `None` if it already is
+fn fmt_file(path: &Path) -> Result<Option<String>, String> {
+    let name = path.display().to_string();
+    let source = std::fs::read_to_string(path).map_err(|e| format!("could not read {name}, {e}\n"))?;
+    let tokens = lexer::tokenize(&source).map_err(|e| e.report(&name, &source))?;
+    if tokens.iter().any(|t| t.kind == lexer::TokenKind::Comment) {
+        return Err(format!("skipping {name}, formatting would remove its comments\n"))
+    }
+    let formatted = match path.extension().and_then(|e| e.to_str()) {
+        Some("scn") => source.parse::<GameScenes>().map_err(|e| e.report(&name, &source))?.to_string(),
+        Some("scr") => source.parse::<Script>().map_err(|e| e.report(&name, &source))?.to_string(),
+        _ => return Err(format!("don't know how to format {name}\n")),
+    };
+    Ok((formatted != source).then_some(formatted))
+}
+/// `check [scene files or directories..]` finds the mistakes in scenes that would otherwise only show up while playing.
+/// every exit has to lead to a scene with an exit to arrive at, and every image, mesh
+/// and dialogue script a scene uses has to load. nothing is drawn so this works without a display.
+/// gives the exit code
+pub fn check(args: &[String]) -> i32 {
+    load_catalogues();
+    let paths = if args.is_empty() { vec![SCENES_PATH.to_string()] } else { args.to_vec() };
+    let mut errors = 0;
+    let mut checked = Vec::new();
+    for path in &paths {
+        let scenes = match GameScenes::load(path) {
+            Ok(scenes) => scenes,
+            Err(e) => {
+                let source = if gltf_import::is_gltf(path) { String::new() } else { std::fs::read_to_string(path).unwrap_or_default() };
+                eprint!("{}", e.report(path, &source));
+                errors += e.count();
+                continue
+            },
+        };
+        for problem in scenes.check_exits() { eprintln!("error: {problem}\n --> {path}\n"); errors += 1 }
+        for scene in scenes.iter() {
+            let assets = scene.image_paths().into_iter().map(|p| ("image", p))
+                .chain(scene.mesh_paths().into_iter().map(|p| ("mesh", p)))
+                .chain(scene.script_paths().into_iter().map(|p| ("script", p)));
+            for (kind, asset) in assets {
+                if checked.contains(&asset) { continue }
+                if let Err(e) = check_asset(kind, &asset) {
+                    eprint!("error: {kind} `{asset}` used in scene `{}` can't be loaded\n --> {path}\n{e}", scene.name);
+                    errors += 1;
+                }
+                checked.push(asset);
+            }
+        }
+    }
+    if errors > 0 { eprintln!("{errors} error(s) found"); 1 }
+    else { println!("checked {}, no errors found", paths.join(", ")); 0 }
+}
+/// loads an asset the same way the game would, up to the point it would be sent to the gpu.
+/// gives what went wrong as a note, or the full report for a script that doesn't parse
+fn check_asset(kind: &str, path: &str) -> Result<(), String> {
+    let note = |e: &dyn std::fmt::Display| format!(" = {e}\n\n");
+    match kind {
+        "image" => { image::ImageReader::open(path).map_err(|e| note(&e))?.decode().map_err(|e| note(&e))?; },
+        // the game leaves out materials too
+        "mesh" => match gltf_import::split_mesh_path(path) {
+            Some((file, name)) => { gltf_import::read_mesh(file, name).map_err(|e| note(&e))?; },
+            None => { let _ = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS).map_err(|e| note(&e))?; },
+        },
+        _ => {
+            let source = std::fs::read_to_string(path).map_err(|e| note(&e))?;
+            Format::of(path).parse::<Script>(&source).map_err(|e| format!("\n{}", e.report(path, &source)))?;
+        },
+    }
+    Ok(())
+}
+/// `convert <from> <to>` rewrites a scene or script file in the format given by the extension of `to`,
+/// see [`Format`]. whether it holds scenes or a script comes from whichever of the two is a `.scn` or `.scr`.
+/// a glTF file can be converted to scenes the way the game would import it.
+/// includes aren't followed and prefabs are kept as prefabs. gives the exit code
+pub fn convert(args: &[String]) -> i32 {
+    let [from, to] = args else { eprintln!("usage: convert <from> <to>"); return 1 };
+    load_catalogues();
+    let kind = [from, to].into_iter().find_map(|p| match Path::new(p).extension()?.to_str()? {
+        kind @ ("scn" | "scr") => Some(kind),
+        _ => None,
+    });
+    let converted = match kind {
+        Some("scn") if gltf_import::is_gltf(from) => gltf_import::import_scenes(from)
+            .map_err(|e| e.report(from, ""))
+            .and_then(|scenes| write_as(from, to, &scenes)),
+        Some("scn") => convert_file::<GameScenes>(from, to),
+        Some(_) => convert_file::<Script>(from, to),
+        None => Err(format!("one of {from} and {to} has to be a .scn or .scr file to know what they hold\n")),
+    };
+    match converted.and_then(|text| std::fs::write(to, text).map_err(|e| format!("could not write {to}, {e}\n"))) {
+        Ok(()) => 0,
+        Err(message) => { eprint!("{message}"); 1 },
+    }
+}
+fn convert_file<T>(from: &str, to: &str) -> Result<String, String>
+where T: FromStr<Err = ParseErr> + DeserializeOwned + Display + Serialize {
+    let source = std::fs::read_to_string(from).map_err(|e| format!("could not read {from}, {e}\n"))?;
+    let value: T = Format::of(from).parse(&source).map_err(|e| e.report(from, &source))?;
+    write_as(from, to, &value)
+}
+fn write_as<T: Display + Serialize>(from: &str, to: &str, value: &T) -> Result<String, String> {
+    Format::of(to).write(value).map_err(|e| format!("could not convert {from}, {e}\n"))
+}
@@ -0,0 +1,176 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use thin_engine::{prelude::*, Display, text_renderer::TextDrawError};
+use crate::{
+    Action::{self, *}, PlayerData, graphics::GraphicsData,
+    screens::{line_pos, SELECTED, UNSELECTED}, file_types::{stats, enemies::{EnemyDef, HEALTH_STAT, STRENGTH_STAT, SPEED_STAT, DECEPTION_STAT}}
+};
+
+/// a small xorshift generator, encounters only need rough dice
+#[derive(Debug, Clone)]
+struct Dice(u64);
+impl Dice {
+    fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Self(nanos | 1)
+    }
+    /// 1 to 6
+    fn roll(&mut self) -> i32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % 6) as i32 + 1
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncounterAction {
+    /// strength against the enemy's speed
+    Attack,
+    /// deception against the enemy's wits, getting out of the encounter
+    Deceive,
+    /// speed against the enemy's speed, getting out of the encounter
+    Run,
+}
+const ACTIONS: [(EncounterAction, &str); 3] = [
+    (EncounterAction::Attack, "Attack"),
+    (EncounterAction::Deceive, "Deceive"),
+    (EncounterAction::Run, "Run"),
+];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome { Won, Lost, Escaped }
+/// a stat by name, the stats encounters use are checked to exist when enemies are loaded
+fn stat(player: &PlayerData, name: &str) -> i32 {
+    stats::stat_schema().find(name).map(|s| player.stat(s).floor() as i32).unwrap_or(0)
+}
+/// a turn-based encounter with one enemy. whoever is faster goes first,
+/// then the player and the enemy take turns until someone wins or the player gets away
+pub struct Encounter {
+    pub enemy: EnemyDef,
+    enemy_health: i32,
+    player_health: i32,
+    dice: Dice,
+    selected: usize,
+    /// what happened in the last round
+    log: Vec<String>,
+    pub outcome: Option<Outcome>,
+}
+impl Encounter {
+    pub fn new(enemy: EnemyDef, player: &PlayerData) -> Self { Self::with_dice(enemy, player, Dice::from_time()) }
+    fn with_dice(enemy: EnemyDef, player: &PlayerData, dice: Dice) -> Self {
+        let mut encounter = Self {
+            enemy_health: enemy.health,
+            player_health: 4 + 2 * stat(player, HEALTH_STAT),
+            log: vec![format!("{} blocks the way", enemy.name)],
+            enemy, dice, selected: 0, outcome: None,
+        };
+        if encounter.enemy.speed > stat(player, SPEED_STAT) { encounter.enemy_turn(player) }
+        encounter
+    }
+    /// plays a round, the player's action and then the enemy's turn if the encounter isn't over
+    pub fn act(&mut self, action: EncounterAction, player: &PlayerData) {
+        if self.outcome.is_some() { return }
+        self.log.clear();
+        let name = self.enemy.name.clone();
+        match action {
+            EncounterAction::Attack => {
+                if self.dice.roll() + stat(player, STRENGTH_STAT) > self.dice.roll() + self.enemy.speed {
+                    let damage = stat(player, STRENGTH_STAT).max(1);
+                    self.enemy_health -= damage;
+                    self.log.push(format!("You hit {name} for {damage}"));
+                } else {
+                    self.log.push(format!("{name} dodges"));
+                }
+                if self.enemy_health <= 0 {
+                    self.log.push(format!("{name} is beaten"));
+                    self.outcome = Some(Outcome::Won);
+                    return
+                }
+            },
+            EncounterAction::Deceive => {
+                if self.dice.roll() + stat(player, DECEPTION_STAT) > self.dice.roll() + self.enemy.wits {
+                    self.log.push(format!("{name} falls for it and lets you go"));
+                    self.outcome = Some(Outcome::Escaped);
+                    return
+                }
+                self.log.push(format!("{name} doesn't believe you"));
+            },
+            EncounterAction::Run => {
+                if self.dice.roll() + stat(player, SPEED_STAT) > self.dice.roll() + self.enemy.speed {
+                    self.log.push("You get away".to_string());
+                    self.outcome = Some(Outcome::Escaped);
+                    return
+                }
+                self.log.push(format!("{name} cuts you off"));
+            },
+        }
+        self.enemy_turn(player)
+    }
+    fn enemy_turn(&mut self, player: &PlayerData) {
+        let name = &self.enemy.name;
+        if self.dice.roll() + self.enemy.strength > self.dice.roll() + stat(player, SPEED_STAT) {
+            let damage = self.enemy.strength.max(1);
+            self.player_health -= damage;
+            self.log.push(format!("{name} hits you for {damage}"));
+        } else {
+            self.log.push(format!("You dodge {name}"));
+        }
+        if self.player_health <= 0 {
+            self.log.push("You're beaten".to_string());
+            self.outcome = Some(Outcome::Lost);
+        }
+    }
+    /// gives the player the tag for how the encounter ended and the enemy's experience if they won,
+    /// giving the stats that went up
+    pub fn finish(&self, player: &mut PlayerData) -> Vec<usize> {
+        let Some(outcome) = self.outcome else { return Vec::new() };
+        let tag = match outcome {
+            Outcome::Won     => &self.enemy.won_tag,
+            Outcome::Lost    => &self.enemy.lost_tag,
+            Outcome::Escaped => &self.enemy.escaped_tag,
+        };
+        if !player.acquired_tags.contains(tag) { player.acquired_tags.push(tag.clone()) }
+        if outcome != Outcome::Won { return Vec::new() }
+        self.enemy.xp.iter().filter(|(stat, amount)| player.gain_xp(*stat, *amount) > 0).map(|(stat, _)| *stat).collect()
+    }
+    /// handles input for the frame, giving true once a finished encounter has been dismissed
+    pub fn update(&mut self, input: &InputMap<Action>, player: &PlayerData) -> bool {
+        if self.outcome.is_some() { return input.pressed(MenuSelect) }
+        if input.pressed(MenuDown) { self.selected = (self.selected + 1) % ACTIONS.len() }
+        if input.pressed(MenuUp)   { self.selected = (self.selected + ACTIONS.len() - 1) % ACTIONS.len() }
+        if input.pressed(MenuSelect) { self.act(ACTIONS[self.selected].0, player) }
+        false
+    }
+    pub fn draw(
+        &self, graphics: &mut GraphicsData, frame: &mut impl Surface, display: &Display
+    ) -> Result<(), TextDrawError> {
+        let mut draw = |text: &str, brightness: f32, line: usize| {
+            graphics.draw_text(frame, display, text, Vec3::splat(brightness), line_pos(line))
+        };
+        draw(&format!("{}: {} health", self.enemy.name, self.enemy_health.max(0)), 0.75, 0)?;
+        draw(&format!("You: {} health", self.player_health.max(0)), 0.75, 1)?;
+        let mut line = 3;
+        for message in &self.log { draw(message, UNSELECTED, line)?; line += 1 }
+        line += 1;
+        if self.outcome.is_some() { return draw("Continue", SELECTED, line) }
+        for (i, (_, action)) in ACTIONS.iter().enumerate() {
+            draw(action, if i == self.selected { SELECTED } else { UNSELECTED }, line + i)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn encounter_turns() {
+    let enemy: EnemyDef = "enemy#guard[health[2], strength[1], speed[10], won[past_guard]]".parse().unwrap();
+    let mut player = PlayerData::default();
+    // the guard is faster so it acts before the player can
+    let encounter = Encounter::with_dice(enemy.clone(), &player, Dice(7));
+    assert_eq!(encounter.log.len(), 2);
+
+    let mut encounter = Encounter::with_dice(enemy, &player, Dice(7));
+    while encounter.outcome.is_none() { encounter.act(EncounterAction::Attack, &player) }
+    encounter.finish(&mut player);
+    let tag = match encounter.outcome.unwrap() {
+        Outcome::Won => "past_guard", Outcome::Lost => "lost_guard", Outcome::Escaped => unreachable!(),
+    };
+    assert_eq!(player.acquired_tags, [tag]);
+}
@@ -0,0 +1,126 @@
+use std::{str::FromStr, fmt::Display, path::PathBuf};
+use crate::{file_types::{*, save::SAVE_DIR}, PlayerData};
+
+pub const ACHIEVEMENTS_PATH: &str = "achievements.ach";
+
+/// every achievement in the game, each unlocked once its `req` is met.
+/// ```text
+/// achievement#poet[title[Poet], icon[smug.png], req[successful_rhyme]]
+/// achievement#fit[title[In Shape], icon[smug.png], req[fitness >= 3]]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Achievements {
+    pub achievements: Vec<Achievement>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    pub icon: Option<String>,
+    pub requirements: Requirements,
+}
+impl Achievements {
+    /// a missing achievements file just means there are no achievements
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    /// unlocks any achievements the player now meets, giving the ones that were just unlocked
+    pub fn check(&self, profile: &mut Profile, data: &PlayerData) -> Vec<&Achievement> {
+        let mut unlocked = Vec::new();
+        for achievement in &self.achievements {
+            if profile.unlocked.contains(&achievement.id) || !achievement.requirements.evaluate(data) { continue }
+            profile.unlocked.push(achievement.id.clone());
+            unlocked.push(achievement);
+        }
+        unlocked
+    }
+}
+impl FromStr for Achievements {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing achievements: {s}") }
+        let achievements = parse_each(&split_args_spanned(s)?, 0, "achievement")?;
+        Ok(Self { achievements })
+    }
+}
+impl FromStr for Achievement {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing achievement: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        let (prefix, id) = prefix.split_once('#').ok_or(ParseErr::NotEnoughArgs)?;
+        if prefix != "achievement" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let mut title = id.trim().to_string();
+        let mut icon = None;
+        let mut requirements = None;
+        for arg in split_args(&inner)? {
+            match split_call(&arg)? {
+                ("title", t) => title = t.trim().to_string(),
+                ("icon",  i) => icon = Some(i.trim().to_string()),
+                ("req",   r) => requirements = Some(r.parse()?),
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(Self { id: id.trim().to_string(), title, icon, requirements: requirements.ok_or(ParseErr::NotEnoughArgs)? })
+    }
+}
+/// what the player has done across every save, written as `profile[achievements[poet, fit]]`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Profile {
+    pub unlocked: Vec<String>,
+}
+impl Profile {
+    pub fn path() -> PathBuf { PathBuf::from(SAVE_DIR).join("profile.prf") }
+    /// a missing profile is a new player with nothing unlocked
+    pub fn load() -> Result<Self, ParseErr> {
+        match std::fs::read_to_string(Self::path()) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(SAVE_DIR)?;
+        std::fs::write(Self::path(), self.to_string())
+    }
+}
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "profile[achievements[{}]]", self.unlocked.join(", "))
+    }
+}
+impl FromStr for Profile {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing profile: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        if prefix != "profile" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let mut profile = Self::default();
+        for arg in split_args(&inner)? {
+            match split_call(&arg)? {
+                ("achievements", a) => profile.unlocked = split_args(&a)?,
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+#[test]
+fn achievements_unlock_once() {
+    let achievements: Achievements = "
+        achievement#poet[title[Poet], req[successful_rhyme]]
+        achievement#fit[title[In Shape], icon[smug.png], req[fitness >= 3]]
+    ".parse().unwrap();
+    let mut profile = Profile::default();
+    let mut data = PlayerData::default();
+    data.acquired_tags.push("successful_rhyme".to_string());
+    let unlocked: Vec<&str> = achievements.check(&mut profile, &data).iter().map(|a| a.id.as_str()).collect();
+    assert_eq!(unlocked, ["poet"]);
+    assert!(achievements.check(&mut profile, &data).is_empty());
+    assert_eq!(profile.to_string().parse::<Profile>().unwrap(), profile);
+}
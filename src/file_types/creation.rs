@@ -0,0 +1,76 @@
+use std::str::FromStr;
+use crate::file_types::*;
+
+pub const CREATION_PATH: &str = "character.chr";
+
+/// what the player can choose from when making their character.
+/// ```text
+/// budget[4]
+/// max[3]
+/// origin#drifter[name[Drifter], description[No ties and no debts], tags[drifter]]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreationConfig {
+    /// points the player spends across their stats
+    pub budget: i32,
+    /// the most points that can go into a single stat
+    pub max_per_stat: i32,
+    pub origins: Vec<Origin>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// given to the player when they start with this origin
+    pub tags: Vec<String>,
+}
+impl Default for CreationConfig {
+    fn default() -> Self { Self { budget: 3, max_per_stat: 3, origins: Vec::new() } }
+}
+impl CreationConfig {
+    /// a missing file gives the default budget and no origins
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+impl FromStr for CreationConfig {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing character creation: {s}") }
+        let mut config = Self::default();
+        for arg in split_args(s)? {
+            let (prefix, inner) = split_call(&arg)?;
+            let parse_int = |s: &str| s.trim().parse().map_err(|_| ParseErr::InvalidIndex(s.to_string()));
+            match prefix.split_once('#') {
+                Some(("origin", id)) => config.origins.push(parse_origin(id, &inner)?),
+                Some((prefix, _)) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+                None => match prefix {
+                    "budget" => config.budget = parse_int(&inner)?,
+                    "max"    => config.max_per_stat = parse_int(&inner)?,
+                    prefix => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+                },
+            }
+        }
+        Ok(config)
+    }
+}
+fn parse_origin(id: &str, s: &str) -> Result<Origin, ParseErr> {
+    if debug_parse() { println!("parsing origin: {s}") }
+    let mut origin = Origin {
+        id: id.trim().to_string(), name: id.trim().to_string(), description: String::new(), tags: Vec::new()
+    };
+    for arg in split_args(s)? {
+        match split_call(&arg)? {
+            ("name", name) => origin.name = name.trim().to_string(),
+            ("description", description) => origin.description = description.trim().to_string(),
+            ("tags", tags) => origin.tags = split_args(&tags)?,
+            (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+        }
+    }
+    Ok(origin)
+}
@@ -0,0 +1,123 @@
+use std::{str::FromStr, sync::{Arc, LazyLock, RwLock}};
+use crate::file_types::*;
+
+pub const ENEMIES_PATH: &str = "enemies.enm";
+/// the player's stats encounters are fought with, see [`check_encounter_stats`]
+pub const HEALTH_STAT:    &str = "fitness";
+pub const STRENGTH_STAT:  &str = "strength";
+pub const SPEED_STAT:     &str = "speed";
+pub const DECEPTION_STAT: &str = "deception";
+
+static ENEMIES: LazyLock<RwLock<Arc<EnemyCatalogue>>> = LazyLock::new(RwLock::default);
+/// the enemies loaded by [`load_enemies`], or no enemies if nothing was loaded
+pub fn enemy_catalogue() -> Arc<EnemyCatalogue> { ENEMIES.read().unwrap().clone() }
+/// replaces the enemies until they're next loaded, giving the ones they replaced
+pub fn set_enemy_catalogue(enemies: EnemyCatalogue) -> Arc<EnemyCatalogue> { std::mem::replace(&mut ENEMIES.write().unwrap(), Arc::new(enemies)) }
+/// loads every enemy in the game, this has to happen after the stat schema is loaded
+/// and before any scenes or scripts are parsed. a missing file means no enemies
+pub fn load_enemies(path: impl AsRef<std::path::Path>) -> Result<(), ParseErr> {
+    let enemies = match std::fs::read_to_string(path) {
+        Ok(s) => s.parse()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => EnemyCatalogue::default(),
+        Err(e) => return Err(e.into()),
+    };
+    set_enemy_catalogue(enemies);
+    Ok(())
+}
+/// everything the player can get into an encounter with.
+/// the tags given for each outcome default to `won_id`, `lost_id` and `escaped_id`.
+/// ```text
+/// enemy#guard[
+///     name[Guard]
+///     health[6], strength[2], speed[1], wits[1]
+///     xp[strength 5]
+///     won[got_past_guard]
+/// ]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EnemyCatalogue {
+    enemies: Vec<EnemyDef>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyDef {
+    pub id: String,
+    pub name: String,
+    pub health: i32,
+    pub strength: i32,
+    pub speed: i32,
+    /// resists the player's deception
+    pub wits: i32,
+    /// experience the player gets for winning
+    pub xp: Vec<(usize, u32)>,
+    pub won_tag: String,
+    pub lost_tag: String,
+    pub escaped_tag: String,
+}
+impl EnemyCatalogue {
+    pub fn find(&self, id: &str) -> Option<&EnemyDef> { self.enemies.iter().find(|e| e.id == id) }
+    pub fn iter(&self) -> impl Iterator<Item = &EnemyDef> { self.enemies.iter() }
+    /// checks an enemy id used by a scene or script exists
+    pub fn check(&self, id: &str) -> Result<String, ParseErr> {
+        match self.find(id) {
+            Some(enemy) => Ok(enemy.id.clone()),
+            None => Err(ParseErr::InvalidEnemy(id.to_string())),
+        }
+    }
+}
+impl FromStr for EnemyCatalogue {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing enemies: {s}") }
+        let enemies: Vec<EnemyDef> = parse_each(&split_args_spanned(s)?, 0, "enemy")?;
+        if !enemies.is_empty() { check_encounter_stats(&stats::stat_schema())? }
+        Ok(Self { enemies })
+    }
+}
+/// the stat schema has to have every stat encounters are fought with for there to be any enemies
+pub fn check_encounter_stats(schema: &stats::StatSchema) -> Result<(), ParseErr> {
+    let missing = [HEALTH_STAT, STRENGTH_STAT, SPEED_STAT, DECEPTION_STAT].into_iter()
+        .filter(|stat| schema.find(stat).is_none())
+        .map(|stat| ParseErr::MissingEncounterStat(stat.to_string()))
+        .collect();
+    any_errors(missing)
+}
+impl FromStr for EnemyDef {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing enemy: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        let (prefix, id) = prefix.split_once('#').ok_or(ParseErr::NotEnoughArgs)?;
+        if prefix != "enemy" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let id = id.trim();
+        let mut enemy = EnemyDef {
+            id: id.to_string(), name: id.to_string(),
+            health: 1, strength: 0, speed: 0, wits: 0, xp: Vec::new(),
+            won_tag: format!("won_{id}"), lost_tag: format!("lost_{id}"), escaped_tag: format!("escaped_{id}"),
+        };
+        for arg in split_args(&inner)? {
+            let (prefix, inner) = split_call(&arg)?;
+            let parse_int = |s: &str| s.trim().parse().map_err(|_| ParseErr::InvalidIndex(s.to_string()));
+            match prefix {
+                "name"     => enemy.name = inner.trim().to_string(),
+                "health"   => enemy.health   = parse_int(&inner)?,
+                "strength" => enemy.strength = parse_int(&inner)?,
+                "speed"    => enemy.speed    = parse_int(&inner)?,
+                "wits"     => enemy.wits     = parse_int(&inner)?,
+                "xp"       => enemy.xp.append(&mut script::parse_xp_gains(&inner)?),
+                "won"      => enemy.won_tag     = inner.trim().to_string(),
+                "lost"     => enemy.lost_tag    = inner.trim().to_string(),
+                "escaped"  => enemy.escaped_tag = inner.trim().to_string(),
+                prefix => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(enemy)
+    }
+}
+
+#[test]
+fn encounter_stats_are_checked() {
+    let schema: stats::StatSchema = "fitness[start[1], strength, speed]".parse().unwrap();
+    let err = check_encounter_stats(&schema).unwrap_err();
+    assert!(matches!(err, ParseErr::MissingEncounterStat(s) if s == DECEPTION_STAT));
+    assert!(check_encounter_stats(&stats::stat_schema()).is_ok());
+}
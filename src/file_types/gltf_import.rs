@@ -0,0 +1,236 @@
+use std::{path::Path, f32::consts::TAU};
+use serde_json::{Map, Value};
+use thin_engine::prelude::*;
+use crate::file_types::{*, scenes::{GameScenes, Scene, Object, ObjectType}};
+
+/// the custom properties that make a node into an object, each holding the arguments as written in a scene
+const KINDS: &[&str] = &["collider", "trigger", "exit", "dialogue", "encounter"];
+
+/// `.gltf` and `.glb` files can be used anywhere a scene file can
+pub fn is_gltf(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|e| e == "gltf" || e == "glb")
+}
+/// the scenes of a glTF file with the hierarchy of their nodes kept as groups.
+/// meshes become `mesh` objects drawn straight from the file with the image from their material,
+/// or from an `image` custom property if the image is packed into the file.
+/// lights become `point_light` and `dir_light` objects, spot lights being treated as point lights.
+/// a node with a custom property named after an object kind becomes that object
+/// and a `camera` custom property on a scene is its camera
+/// ```text
+/// exit: hallway, door, trigger[cube]
+/// dialogue: poca.scr, trigger[sphere], prompt[Talk]
+/// camera: pos[0, 0, 10], rot[0, 180, 0]
+/// ```
+pub fn import_scenes(path: impl AsRef<Path>) -> Result<GameScenes, ParseErr> {
+    let path = path.as_ref();
+    let gltf = gltf::Gltf::open(path)?;
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let count = gltf.document.scenes().len();
+    let mut scenes = Vec::new();
+    let mut errs = Vec::new();
+    for scene in gltf.document.scenes() {
+        let name = match scene.name() {
+            Some(name) => name.to_string(),
+            None if count == 1 => stem.clone(),
+            None => format!("{stem}{}", scene.index()),
+        };
+        let camera = properties(scene.extras()).and_then(|props| match props.get("camera") {
+            Some(camera) => scenes::parse_camera(&format!("camera[{}]", text(camera, "camera")?)),
+            None => Ok((Vec3::ZERO, Vec3::ZERO, Vec3::ONE)),
+        });
+        let mut objects = Vec::new();
+        for node in scene.nodes() {
+            match node_object(&node, path) {
+                Ok(Some(o)) => objects.push(o),
+                Ok(None) => (),
+                Err(e) => errs.push(e.context(format!("scene `#{name}`"), 0..0)),
+            }
+        }
+        match camera {
+            Ok((pos, rot, scale)) => scenes.push(Scene::new(name, objects, pos, rot, scale)),
+            Err(e) => errs.push(e.context("camera", 0..0).context(format!("scene `#{name}`"), 0..0)),
+        }
+    }
+    any_errors(errs)?;
+    GameScenes::from_scenes(scenes)
+}
+/// a node and its children as one object, or nothing if there's nothing to place
+fn node_object(node: &gltf::Node, path: &Path) -> Result<Option<Object>, ParseErr> {
+    let name = node.name().unwrap_or_default().to_string();
+    let context = |e: ParseErr| e.context(format!("node `{name}`"), 0..0);
+    let props = properties(node.extras()).map_err(context)?;
+    let mut objects = Vec::new();
+    if let Some(mesh) = node.mesh() {
+        let mesh_name = mesh.name().map_or_else(|| mesh.index().to_string(), str::to_string);
+        let image_path = match props.get("image") {
+            Some(image) => text(image, "image").map_err(context)?.to_string(),
+            None => material_image(&mesh, path).ok_or_else(|| context(ParseErr::InvalidData(
+                format!("mesh `{mesh_name}` has no image, give its node an `image` custom property")
+            )))?,
+        };
+        let mesh_path = format!("{}#{mesh_name}", path.display());
+        objects.push(Object::new(String::new(), ObjectType::Mesh { mesh_path, image_path }, Vec3::ZERO, Vec3::ZERO, Vec3::ONE));
+    }
+    if let Some(light) = node.light() {
+        let strength = light.intensity();
+        let colour = Vec3::from(light.color()).scale(255.0);
+        objects.push(match light.kind() {
+            // glTF lights shine down -z, ours light up whatever is facing their y
+            gltf::khr_lights_punctual::Kind::Directional => Object::new(
+                String::new(), ObjectType::DirLight { strength, colour }, Vec3::ZERO, vec3(90.0, 0.0, 0.0), Vec3::ONE
+            ),
+            _ => Object::new(String::new(), ObjectType::PointLight { strength, colour }, Vec3::ZERO, Vec3::ZERO, Vec3::ONE),
+        });
+    }
+    for (kind, args) in props.iter().filter(|(kind, _)| KINDS.contains(&kind.as_str())) {
+        let object = format!("{kind}#{name}[{}]", text(args, kind).map_err(context)?);
+        objects.push(object.parse().map_err(|e: ParseErr| context(e.context(format!("property `{kind}`"), 0..0)))?);
+    }
+    for child in node.children() {
+        if let Some(o) = node_object(&child, path).map_err(context)? { objects.push(o) }
+    }
+    let (pos, rot, scale) = transform(node);
+    // a node with one object in it is that object, unless it has to be moved within the node
+    let object_type = match objects.pop() {
+        None => return Ok(None),
+        Some(o) if objects.is_empty() && o.local_transform() == (Vec3::ZERO, Vec3::ZERO, Vec3::ONE) => o.object_type,
+        Some(o) => { objects.push(o); ObjectType::Group { objects } },
+    };
+    Ok(Some(Object::new(name, object_type, pos, rot, scale)))
+}
+/// the custom properties of a node or scene
+fn properties(extras: &gltf::json::Extras) -> Result<Map<String, Value>, ParseErr> {
+    let Some(extras) = extras else { return Ok(Map::new()) };
+    match serde_json::from_str(extras.get()) {
+        Ok(Value::Object(props)) => Ok(props),
+        Ok(_) => Ok(Map::new()),
+        Err(e) => Err(ParseErr::InvalidData(e.to_string())),
+    }
+}
+fn text<'a>(value: &'a Value, property: &str) -> Result<&'a str, ParseErr> {
+    value.as_str().ok_or_else(|| ParseErr::InvalidData(format!("custom property `{property}` has to be text")))
+}
+/// the image used by the first material of a mesh, if it's a file of its own
+fn material_image(mesh: &gltf::Mesh, path: &Path) -> Option<String> {
+    let texture = mesh.primitives().next()?.material().pbr_metallic_roughness().base_color_texture()?;
+    let gltf::image::Source::Uri { uri, .. } = texture.texture().source().source() else { return None };
+    Some(path.parent().unwrap_or(Path::new("")).join(uri).display().to_string())
+}
+/// `pos`, `rot` in degrees and `scale` of a node, with the rotation split up in the order [`Object::new`] puts it back together
+fn transform(node: &gltf::Node) -> (Vec3, Vec3, Vec3) {
+    let (pos, [i, j, k, r], scale) = node.transform().decomposed();
+    // the parts of the rotation matrix needed, see `Mat4::from_rot`
+    let m02 = 2.0*(i*k + j*r);
+    let m12 = 2.0*(j*k - i*r);
+    let m22 = 1.0 - 2.0*(i*i + j*j);
+    let m01 = 2.0*(i*j - k*r);
+    let m00 = 1.0 - 2.0*(j*j + k*k);
+    let m10 = 2.0*(i*j + k*r);
+    let m11 = 1.0 - 2.0*(i*i + k*k);
+    // rather than the arcsine of m02, which loses precision close to straight up or down
+    let y = m02.atan2(m00.hypot(m01));
+    // straight up or down only the sum of the x and z rotations matters
+    let (x, z) = if m02.abs() < 0.9999 { ((-m12).atan2(m22), (-m01).atan2(m00)) } else { (0.0, m10.atan2(m11)) };
+    let degrees = |a: f32| (a * 360.0/TAU * 1000.0).round() / 1000.0 + 0.0;
+    (pos.into(), vec3(degrees(x), degrees(y), degrees(z)), scale.into())
+}
+/// a mesh in a glTF file written as `file#mesh`, by name or by index
+pub fn split_mesh_path(path: &str) -> Option<(&str, &str)> {
+    path.rsplit_once('#').filter(|(file, _)| is_gltf(file))
+}
+/// the parts of a glTF mesh the game draws, one for each of its primitives.
+/// texture coordinates are flipped to match images loaded for `.obj` meshes,
+/// and are all zero if the primitive has none. missing normals are worked out from the faces
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub normals: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+}
+pub fn read_mesh(file: &str, name: &str) -> Result<Vec<MeshData>, ParseErr> {
+    let gltf = gltf::Gltf::open(file)?;
+    let buffers = gltf::import_buffers(&gltf.document, Path::new(file).parent(), gltf.blob.clone())?;
+    let mesh = gltf.document.meshes().find(|m| m.name() == Some(name))
+        .or_else(|| gltf.document.meshes().nth(name.parse().ok()?))
+        .ok_or_else(|| ParseErr::InvalidData(format!("`{file}` has no mesh `{name}`")))?;
+    Ok(mesh.primitives().map(|primitive| {
+        let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+        let positions: Vec<[f32; 3]> = reader.read_positions().map(Iterator::collect).unwrap_or_default();
+        let indices: Vec<u32> = reader.read_indices().map(|i| i.into_u32().collect())
+            .unwrap_or_else(|| (0..positions.len() as u32).collect());
+        MeshData {
+            uvs: reader.read_tex_coords(0).map(|uvs| uvs.into_f32().map(|[u, v]| [u, 1.0 - v]).collect())
+                .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]),
+            normals: reader.read_normals().map(Iterator::collect)
+                .unwrap_or_else(|| face_normals(&positions, &indices)),
+            indices,
+            positions,
+        }
+    }).collect())
+}
+/// the normal of each vertex as the average of the faces it's part of, straight up for one that isn't in any
+fn face_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[face[i] as usize]));
+        let normal = (b - a).cross(c - a);
+        for i in face { normals[*i as usize] += normal }
+    }
+    normals.into_iter()
+        .map(|n| if n.length() > 0.0 { n.normalise().into() } else { [0.0, 1.0, 0.0] })
+        .collect()
+}
+
+#[test]
+fn gltf_nodes_become_objects() {
+    use scenes::ObjectType::*;
+    let dir = std::env::temp_dir().join(format!("sword-ward-gltf-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("yard.gltf");
+    // a triangle from (0, 0, 0) to (1, 0, 0) to (0, 1, 0)
+    let triangle = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA";
+    std::fs::write(&path, format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {{ "KHR_lights_punctual": {{ "lights": [{{ "type": "point", "color": [1, 0.5, 0], "intensity": 3 }}] }} }},
+        "scene": 0,
+        "scenes": [{{ "name": "yard", "nodes": [0, 1, 2], "extras": {{ "camera": "pos[0, 2, 10]" }} }}],
+        "nodes": [
+            {{ "name": "gate", "translation": [1, 0, 2], "rotation": [0, 0.70710677, 0, 0.70710677], "scale": [1, 2, 1],
+                "extras": {{ "exit": "hall, door, trigger[cube]" }} }},
+            {{ "name": "lamp", "extensions": {{ "KHR_lights_punctual": {{ "light": 0 }} }} }},
+            {{ "name": "rock", "mesh": 0, "children": [3], "extras": {{ "image": "rock.png" }} }},
+            {{ "name": "wall", "translation": [0, 1, 0], "extras": {{ "collider": "cube" }} }}
+        ],
+        "meshes": [{{ "name": "Rock", "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+        "accessors": [{{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }}],
+        "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+        "buffers": [{{ "byteLength": 36, "uri": "data:application/octet-stream;base64,{triangle}" }}]
+    }}"#)).unwrap();
+    let scenes = GameScenes::load(&path).unwrap();
+    let yard = &scenes["yard"];
+    assert_eq!(yard.cam_pos, vec3(0.0, 2.0, 10.0));
+    let objects = yard.all_objects();
+    let find = |name: &str| objects.iter().find(|(o, _)| o.name == name).unwrap();
+
+    let (gate, t) = find("gate");
+    assert!(matches!(&gate.object_type, Exit { exit_scene, .. } if exit_scene == "hall"));
+    assert_eq!(gate.local_transform().1, vec3(0.0, 90.0, 0.0));
+    let expected = Mat4::from_transform(vec3(1.0, 0.0, 2.0), vec3(1.0, 2.0, 1.0), Quat::from_y_rot(TAU/4.0));
+    for row in 0..4 { for col in 0..4 { assert!((t[row][col] - expected[row][col]).abs() < 1e-4) } }
+
+    assert!(matches!(find("lamp").0.object_type, PointLight { strength: 3.0, colour } if colour == vec3(255.0, 127.5, 0.0)));
+    let (rock, _) = find("rock");
+    let Group { objects: parts } = &rock.object_type else { panic!("{rock:?}") };
+    let mesh_path = format!("{}#Rock", path.display());
+    assert!(matches!(&parts[0].object_type, Mesh { mesh_path: m, image_path } if *m == mesh_path && image_path == "rock.png"));
+    assert_eq!(find("wall").1.position(), vec3(0.0, 1.0, 0.0));
+
+    let (file, name) = split_mesh_path(&mesh_path).unwrap();
+    let [triangle] = read_mesh(file, name).unwrap().try_into().ok().unwrap();
+    assert_eq!((triangle.positions[1], triangle.indices), ([1.0, 0.0, 0.0], vec![0, 1, 2]));
+    // the triangle has no texture coordinates or normals of its own
+    assert_eq!(triangle.uvs, [[0.0, 0.0]; 3]);
+    assert_eq!(triangle.normals, [[0.0, 0.0, 1.0]; 3]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,111 @@
+use std::{str::FromStr, sync::{Arc, LazyLock, RwLock}};
+use crate::file_types::*;
+
+pub const ITEMS_PATH: &str = "items.itm";
+
+static ITEMS: LazyLock<RwLock<Arc<ItemCatalogue>>> = LazyLock::new(RwLock::default);
+/// the items loaded by [`load_items`], or no items if nothing was loaded
+pub fn item_catalogue() -> Arc<ItemCatalogue> { ITEMS.read().unwrap().clone() }
+/// replaces the items until they're next loaded, giving the ones they replaced
+pub fn set_item_catalogue(items: ItemCatalogue) -> Arc<ItemCatalogue> { std::mem::replace(&mut ITEMS.write().unwrap(), Arc::new(items)) }
+/// loads every item in the game, like the stat schema this has to happen before any scenes
+/// or scripts are parsed as item ids in them are checked against it. a missing file means no items
+pub fn load_items(path: impl AsRef<std::path::Path>) -> Result<(), ParseErr> {
+    let items = match std::fs::read_to_string(path) {
+        Ok(s) => s.parse()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ItemCatalogue::default(),
+        Err(e) => return Err(e.into()),
+    };
+    set_item_catalogue(items);
+    Ok(())
+}
+/// every item the player can carry.
+/// ```text
+/// item#key[name[Brass Key], description[Opens the back door], icon[key.png]]
+/// item#coin[name[Coin], icon[coin.png], stackable]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ItemCatalogue {
+    items: Vec<ItemDef>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub icon: Option<String>,
+    /// whether the player can hold more than one
+    pub stackable: bool,
+}
+impl ItemCatalogue {
+    pub fn find(&self, id: &str) -> Option<&ItemDef> { self.items.iter().find(|i| i.id == id) }
+    /// checks an item id used by a scene or script exists
+    pub fn check(&self, id: &str) -> Result<String, ParseErr> {
+        match self.find(id) {
+            Some(item) => Ok(item.id.clone()),
+            None => Err(ParseErr::InvalidItem(id.to_string())),
+        }
+    }
+}
+impl FromStr for ItemCatalogue {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing items: {s}") }
+        let args = split_args_spanned(s)?;
+        let items: Vec<ItemDef> = parse_each(&args, 0, "item")?;
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|other| other.id == item.id) {
+                return Err(ParseErr::DuplicateItem(item.id.clone()).context(format!("item `{}`", item.id), args[i].1.clone()))
+            }
+        }
+        Ok(Self { items })
+    }
+}
+impl FromStr for ItemDef {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing item: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        let (prefix, id) = prefix.split_once('#').ok_or(ParseErr::NotEnoughArgs)?;
+        if prefix != "item" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let id = id.trim().to_string();
+        let mut item = ItemDef { name: id.clone(), id, description: String::new(), icon: None, stackable: false };
+        for arg in split_args(&inner)? {
+            if arg == "stackable" { item.stackable = true; continue }
+            match split_call(&arg)? {
+                ("name", name) => item.name = name.trim().to_string(),
+                ("description", description) => item.description = description.trim().to_string(),
+                ("icon", icon) => item.icon = Some(icon.trim().to_string()),
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(item)
+    }
+}
+/// item changes from `$give[coin 3 key 1]` and `$take[...]` in scripts
+pub fn parse_item_changes(s: &str) -> Result<Vec<(String, u32)>, ParseErr> {
+    if debug_parse() { println!("parsing item changes: {s}") }
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let mut changes = Vec::new();
+    for pair in words.chunks(2) {
+        let [item, amount] = pair else { return Err(ParseErr::NotEnoughArgs) };
+        let amount = amount.parse().map_err(|_| ParseErr::InvalidIndex(amount.to_string()))?;
+        changes.push((item_catalogue().check(item)?, amount));
+    }
+    Ok(changes)
+}
+
+#[test]
+fn item_definitions() {
+    let items: ItemCatalogue = "
+        item#key[name[Brass Key], description[Opens the back door], icon[key.png]]
+        item#coin[stackable]
+    ".parse().unwrap();
+    let key = items.find("key").unwrap();
+    assert_eq!(key.name, "Brass Key");
+    assert_eq!(key.icon.as_deref(), Some("key.png"));
+    assert!(!key.stackable);
+    assert!(items.find("coin").unwrap().stackable);
+    assert!(items.check("sword").is_err());
+    assert!("item#a[], item#a[]".parse::<ItemCatalogue>().is_err());
+}
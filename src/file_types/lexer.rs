@@ -0,0 +1,146 @@
+use std::ops::Range;
+use crate::file_types::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Open,
+    Close,
+    /// `,` or a new line, both separate arguments
+    Separator,
+    /// any other run of text, escaped characters like `\[` are kept as they are
+    Text,
+    /// a `"quoted string"` with its escapes applied
+    Quoted(String),
+    /// `#` and whitespace starts a comment that goes to the end of the line,
+    /// a `#` straight before a name like `exit#door` is part of the text
+    Comment,
+}
+/// a token along with where it is in the source, in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+/// splits the bracket formats used by every file type into tokens
+pub fn tokenize(s: &str) -> Result<Vec<Token>, ParseErr> { lex(s, false) }
+/// [`tokenize`], but a `"` that's never closed is plain text rather than an error.
+/// dialogue is free text, so a lone quote in it shouldn't stop a script from loading
+pub fn tokenize_lenient(s: &str) -> Vec<Token> { lex(s, true).unwrap_or_default() }
+fn lex(s: &str, lenient: bool) -> Result<Vec<Token>, ParseErr> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let mut push = |kind: TokenKind, span: Range<usize>| match tokens.last_mut() {
+        Some(Token { kind: TokenKind::Text, span: last }) if kind == TokenKind::Text && last.end == span.start =>
+            last.end = span.end,
+        _ => tokens.push(Token { kind, span }),
+    };
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '[' => push(TokenKind::Open, i..i+1),
+            ']' => push(TokenKind::Close, i..i+1),
+            ',' | '\n' => push(TokenKind::Separator, i..i+1),
+            '\\' => {
+                let end = chars.next().map(|(j, c)| j + c.len_utf8()).unwrap_or(s.len());
+                push(TokenKind::Text, i..end);
+            },
+            '"' => {
+                let after_quote = chars.clone();
+                let mut quoted = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((j, '"')) => break Some(j + 1),
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => quoted.push('\n'),
+                            Some((_, 't')) => quoted.push('\t'),
+                            Some((_, c)) => quoted.push(c),
+                            None => break None,
+                        },
+                        Some((_, c)) => quoted.push(c),
+                        None => break None,
+                    }
+                };
+                match end {
+                    Some(end) => push(TokenKind::Quoted(quoted), i..end),
+                    None if lenient => { chars = after_quote; push(TokenKind::Text, i..i+1) },
+                    None => return Err(ParseErr::UnclosedQuote),
+                }
+            },
+            '#' if starts_comment(s, i) => {
+                let end = s[i..].find('\n').map(|j| i + j).unwrap_or(s.len());
+                while chars.peek().is_some_and(|(j, _)| *j < end) { chars.next(); }
+                push(TokenKind::Comment, i..end);
+            },
+            c => push(TokenKind::Text, i..i+c.len_utf8()),
+        }
+    }
+    Ok(tokens)
+}
+fn starts_comment(s: &str, i: usize) -> bool {
+    let after  = s[i+1..].chars().next();
+    let before = s[..i].chars().next_back();
+    after.is_none_or(char::is_whitespace) && before.is_none_or(|c| c.is_whitespace() || c == '[' || c == ',')
+}
+/// the source with its comments blanked out with spaces, so everything else stays where it was
+pub fn strip_comments(s: &str) -> String {
+    tokenize_lenient(s).iter()
+        .map(|t| if t.kind == TokenKind::Comment { " ".repeat(t.span.len()) } else { s[t.span.clone()].to_string() })
+        .collect()
+}
+/// the contents of `s` if all it contains is a quoted string
+pub fn unquote(s: &str) -> Option<String> {
+    let tokens = tokenize(s).ok()?;
+    let mut quoted = None;
+    for t in tokens {
+        match t.kind {
+            TokenKind::Quoted(q) if quoted.is_none() => quoted = Some(q),
+            TokenKind::Text if s[t.span].trim().is_empty() => (),
+            _ => return None,
+        }
+    }
+    quoted
+}
+/// escapes the characters the lexer would otherwise treat as brackets, quotes or comments
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '[' | ']' | '"' | '#' | '\\') { escaped.push('\\') }
+        escaped.push(c);
+    }
+    escaped
+}
+/// `s` as a quoted string if it can't be written as it is, see [`unquote`]
+pub fn quote(s: &str) -> String {
+    let plain = !s.is_empty() && s.trim() == s
+        && !s.contains(['[', ']', '"', '#', '\\', ',', '\n', '\t']);
+    if plain { return s.to_string() }
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"'  => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[test]
+fn lexer_handles_unicode_comments_and_quotes() {
+    let s = "naïve[é, \"a, [b]\\\"\"] # comment ]\nexit#door[—]";
+    let kinds: Vec<TokenKind> = tokenize(s).unwrap().into_iter().map(|t| t.kind).collect();
+    use TokenKind::*;
+    assert_eq!(kinds, [
+        Text, Open, Text, Separator, Text, Quoted("a, [b]\"".to_string()), Close, Text, Comment, Separator,
+        Text, Open, Text, Close,
+    ]);
+    assert_eq!(strip_comments("a # b ]\nc"), "a      \nc");
+    assert_eq!(unquote(" \"x, y\" ").as_deref(), Some("x, y"));
+    assert!(tokenize("\"open").is_err());
+    let kinds: Vec<TokenKind> = tokenize_lenient("\"open [x]").into_iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, [Text, Open, Text, Close]);
+    assert_eq!(unquote(&quote("a, \"b\"\n")).as_deref(), Some("a, \"b\"\n"));
+    assert_eq!(quote("plain"), "plain");
+}
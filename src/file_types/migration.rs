@@ -0,0 +1,206 @@
+use std::str::FromStr;
+use crate::file_types::{*, save::SaveData, creation::Origin, scenes::{GameScenes, ObjectType, Reward}, script::{Script, Segment}};
+
+pub const MIGRATIONS_PATH: &str = "migrations.mig";
+
+/// renames declared by content authors so old saves keep working.
+/// each migration is numbered and only applied to saves made before it.
+/// ```text
+/// migration#1[
+///     tag[ok, mood_ok]
+///     scene[other, hallway]
+///     script[old.scr, new.scr]
+///     stat[luck, fortune]
+///     item[old_key, brass_key]
+/// ]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub version: u32,
+    pub renames: Vec<Rename>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rename {
+    Tag   (String, String),
+    Scene (String, String),
+    Script(String, String),
+    Stat  (String, String),
+    Item  (String, String),
+}
+impl Migrations {
+    /// a missing migrations file just means nothing has been renamed yet
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    /// the content version new saves are written with
+    pub fn latest(&self) -> u32 { self.steps.last().map(|m| m.version).unwrap_or(0) }
+    /// brings a save up to the latest content version
+    pub fn apply(&self, save: &mut SaveData) {
+        let from = save.content_version;
+        for step in self.steps.iter().filter(|m| m.version > from) {
+            for rename in &step.renames { rename.apply(save) }
+        }
+        save.content_version = save.content_version.max(self.latest());
+    }
+}
+impl Rename {
+    fn apply(&self, save: &mut SaveData) {
+        let rename = |list: &mut Vec<String>, from: &str, to: &str| for i in list.iter_mut() {
+            if i == from { *i = to.to_string() }
+        };
+        match self {
+            Self::Tag(from, to) => rename(&mut save.player.acquired_tags, from, to),
+            Self::Scene(from, to) => {
+                if &save.scene == from { save.scene = to.clone() }
+                for key in &mut save.player.picked_up {
+                    if let Some(name) = key.strip_prefix(&format!("{from}#")) { *key = format!("{to}#{name}") }
+                }
+            },
+            Self::Script(from, to) => {
+                rename(&mut save.player.read_scripts, from, to);
+                if let Some(dialogue) = &mut save.dialogue {
+                    if &dialogue.script_path == from { dialogue.script_path = to.clone() }
+                }
+            },
+            Self::Item(from, to) => for (item, _) in &mut save.player.inventory {
+                if item == from { *item = to.clone() }
+            },
+            Self::Stat(from, to) => {
+                let Some(stat) = stats::stat_schema().find(to) else { return };
+                if let Some(i) = save.unknown_stats.iter().position(|(name, _)| name == from) {
+                    save.player.stats[stat] = save.unknown_stats.remove(i).1;
+                }
+            },
+        }
+    }
+}
+/// describes anything the save refers to that the current content no longer has.
+/// tags count as given if a script, pickup, origin or encounter can give them
+pub fn missing_references(save: &SaveData, scenes: &GameScenes, origins: &[Origin]) -> Vec<String> {
+    let mut missing = Vec::new();
+    if scenes.find(&save.scene).is_none() { missing.push(format!("scene `{}` doesn't exist", save.scene)) }
+    for (stat, _) in &save.unknown_stats { missing.push(format!("stat `{stat}` doesn't exist")) }
+
+    let mut known_tags = Vec::new();
+    let mut script_paths = Vec::new();
+    for scene in scenes.iter() { script_paths.append(&mut scene.script_paths()) }
+    for path in &script_paths {
+        if let Ok(script) = Script::from_file(path) {
+            for segment in &script.segments { collect_tags(segment, &mut known_tags) }
+        }
+    }
+    for (o, _) in scenes.iter().flat_map(|scene| scene.all_objects()) {
+        if let ObjectType::Pickup { reward: Reward::Tag(tag), .. } = &o.object_type { known_tags.push(tag.clone()) }
+    }
+    for origin in origins { known_tags.extend(origin.tags.iter().cloned()) }
+    for enemy in enemies::enemy_catalogue().iter() {
+        known_tags.extend([enemy.won_tag.clone(), enemy.lost_tag.clone(), enemy.escaped_tag.clone()]);
+    }
+    for tag in &save.player.acquired_tags {
+        if !known_tags.contains(tag) { missing.push(format!("tag `{tag}` is never given")) }
+    }
+    for (item, _) in save.player.inventory() {
+        if items::item_catalogue().find(item).is_none() { missing.push(format!("item `{item}` doesn't exist")) }
+    }
+    for script in &save.player.read_scripts {
+        if !script_paths.contains(script) { missing.push(format!("script `{script}` isn't used by any scene")) }
+    }
+    if let Some(dialogue) = &save.dialogue {
+        if !script_paths.contains(&dialogue.script_path) {
+            missing.push(format!("current dialogue `{}` isn't used by any scene", dialogue.script_path))
+        }
+    }
+    missing
+}
+fn collect_tags(segment: &Segment, tags: &mut Vec<String>) {
+    tags.extend(segment.add_tags.iter().cloned());
+    for option in &segment.options { collect_tags(option, tags) }
+}
+impl FromStr for Migrations {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing migrations: {s}") }
+        let mut steps = Vec::new();
+        for arg in split_args(s)? { steps.push(arg.parse::<Migration>()?) }
+        steps.sort_by_key(|m| m.version);
+        Ok(Self { steps })
+    }
+}
+impl FromStr for Migration {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing migration: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        let (prefix, version) = prefix.split_once('#').ok_or(ParseErr::NotEnoughArgs)?;
+        if prefix != "migration" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let version = version.trim().parse().map_err(|_| ParseErr::InvalidIndex(version.to_string()))?;
+        let mut renames = Vec::new();
+        for arg in split_args(&inner)? {
+            let (kind, names) = split_call(&arg)?;
+            let (from, to) = match split_args(&names)?.as_slice() {
+                [from, to] => (from.clone(), to.clone()),
+                [_, _, _, ..] => return Err(ParseErr::ToManyArgs),
+                _ => return Err(ParseErr::NotEnoughArgs),
+            };
+            renames.push(match kind {
+                "tag"    => Rename::Tag(from, to),
+                "scene"  => Rename::Scene(from, to),
+                "script" => Rename::Script(from, to),
+                "stat"   => Rename::Stat(from, to),
+                "item"   => Rename::Item(from, to),
+                kind => return Err(ParseErr::InvalidPrefix(kind.to_string())),
+            });
+        }
+        Ok(Self { version, renames })
+    }
+}
+
+#[test]
+fn migrations_apply_in_order() {
+    use crate::{PlayerData, file_types::save::SAVE_VERSION};
+    let migrations: Migrations = "
+        migration#2[tag[mood_ok, mood_fine]]
+        migration#1[tag[ok, mood_ok], scene[other, hallway]]
+    ".parse().unwrap();
+    assert_eq!(migrations.latest(), 2);
+    let mut save = SaveData {
+        version: SAVE_VERSION,
+        content_version: 0,
+        scene: "other".to_string(),
+        player: PlayerData {
+            acquired_tags: vec!["ok".to_string(), "yay".to_string()],
+            ..PlayerData::default()
+        },
+        dialogue: None,
+        unknown_stats: Vec::new(),
+    };
+    migrations.apply(&mut save);
+    assert_eq!(save.scene, "hallway");
+    assert_eq!(save.player.acquired_tags, ["mood_fine", "yay"]);
+    assert_eq!(save.content_version, 2);
+}
+#[test]
+fn tags_from_pickups_are_known() {
+    use crate::{PlayerData, file_types::save::SAVE_VERSION};
+    let scenes: GameScenes = "#room[camera[], pickup[tag[found_note], trigger[sphere]]]".parse().unwrap();
+    let save = SaveData {
+        version: SAVE_VERSION,
+        content_version: 0,
+        scene: "room".to_string(),
+        player: PlayerData {
+            acquired_tags: vec!["found_note".to_string(), "lost_note".to_string()],
+            ..PlayerData::default()
+        },
+        dialogue: None,
+        unknown_stats: Vec::new(),
+    };
+    assert_eq!(missing_references(&save, &scenes, &[]), ["tag `lost_note` is never given"]);
+}
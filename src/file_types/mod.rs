@@ -1,7 +1,19 @@
 pub mod script;
 pub mod scenes;
-use std::{str::FromStr, num::ParseFloatError};
+pub mod save;
+pub mod migration;
+pub mod stats;
+pub mod creation;
+pub mod items;
+pub mod quests;
+pub mod achievements;
+pub mod enemies;
+pub mod lexer;
+pub mod gltf_import;
+use std::{str::FromStr, num::ParseFloatError, ops::Range, fmt::Display, collections::HashMap, path::Path};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use crate::PlayerData;
+use lexer::TokenKind;
 use thin_engine::prelude::*;
 #[derive(Debug)]
 pub enum ParseErr {
@@ -14,44 +26,311 @@ pub enum ParseErr {
     InvalidPrefix(String),
     InvalidColliderType(String),
     InvalidNumber(ParseFloatError),
+    InvalidIndex(String),
+    UnsupportedVersion(u32),
+    InvalidStat(String),
+    InvalidFormula(String),
+    DuplicateStat(String),
+    InvalidItem(String),
+    DuplicateItem(String),
+    InvalidEnemy(String),
+    /// a stat encounters are fought with that the stat schema doesn't have
+    MissingEncounterStat(String),
+    DuplicateScene(String),
+    InvalidPrefab(String),
+    DuplicatePrefab(String),
+    /// two pickups in a scene that would be remembered as the same one
+    DuplicatePickup(String),
+    /// a prefab that ends up using itself
+    RecursivePrefab(String),
+    /// a `$param` in a prefab that the `use` doesn't give a value for
+    MissingParam(String),
+    /// a value given by a `use` that the prefab doesn't have a `$param` for
+    InvalidParam(String),
     IoError(std::io::Error),
     InvalidComparison,
     InvalidColour,
+    UnclosedQuote,
+    /// a `.ron`, `.json` or glTF file that doesn't hold what it should
+    InvalidData(String),
+    GltfError(gltf::Error),
+    /// an error inside part of a file. `span` is where that part is in the text
+    /// of the part containing it, so the spans down a chain add up to a place in the file
+    Context { context: String, span: Range<usize>, err: Box<ParseErr> },
+    /// every error found when the parser could carry on past the first
+    Many(Vec<ParseErr>),
+    /// an error in a file other than the one being loaded, see [`ParseErr::in_file`]
+    InFile { path: String, err: Box<ParseErr> },
+}
+/// the parts of a file an error is inside of, outermost first
+type Chain<'a> = Vec<(&'a str, Range<usize>)>;
+impl ParseErr {
+    pub fn context(self, context: impl Into<String>, span: Range<usize>) -> Self {
+        Self::Context { context: context.into(), span, err: Box::new(self) }
+    }
+    /// the error happened in another file, like one a scene file includes
+    pub fn in_file(self, path: impl Into<String>) -> Self {
+        Self::InFile { path: path.into(), err: Box::new(self) }
+    }
+    /// every error that went wrong along with the parts of the file it was inside of
+    fn flatten<'a>(&'a self, file: Option<&'a str>, chain: &mut Chain<'a>, out: &mut Vec<Location<'a>>) {
+        match self {
+            Self::Context { context, span, err } => {
+                chain.push((context, span.clone()));
+                err.flatten(file, chain, out);
+                chain.pop();
+            },
+            // spans in another file start again from the top of it
+            Self::InFile { path, err } => err.flatten(Some(path), &mut Vec::new(), out),
+            Self::Many(errs) => for err in errs { err.flatten(file, chain, out) },
+            err => out.push(Location {
+                file,
+                span: chain.last().map(|(_, last)| {
+                    let start = chain.iter().map(|(_, span)| span.start).sum::<usize>();
+                    start..start + last.len()
+                }),
+                chain: chain.iter().map(|(context, _)| *context).collect(),
+                err,
+            }),
+        }
+    }
+    pub fn count(&self) -> usize { self.locations().len() }
+    /// compiler style messages for every error, pointing at where it is in `source`.
+    /// errors in other files are pointed at in those files instead
+    /// ```text
+    /// error: invalid collider type `cub`
+    ///  --> test.scn:3:36
+    ///   |
+    /// 3 |     exit#door[other, door, trigger[cub]]
+    ///   |                                    ^^^
+    ///   = in scene `#test` > object `exit#door` > object `trigger` > argument `cub`
+    /// ```
+    pub fn report(&self, path: &str, source: &str) -> String {
+        let mut report = String::new();
+        let mut sources: HashMap<&str, String> = HashMap::new();
+        for Location { file, span, chain, err } in self.locations() {
+            report += &format!("error: {err}\n");
+            let (path, source) = match file {
+                Some(file) if file != path => (file, sources.entry(file)
+                    .or_insert_with(|| std::fs::read_to_string(file).unwrap_or_default()).as_str()),
+                _ => (path, source),
+            };
+            // files that aren't text, like glTF, can only be pointed at by what an error is inside of
+            let Some(span) = span.filter(|_| !source.is_empty()) else {
+                report += &format!("  --> {path}\n");
+                if !chain.is_empty() { report += &format!("   = in {}\n", chain.join(" > ")) }
+                report += "\n";
+                continue
+            };
+            let start = span.start.min(source.len());
+            let end = span.end.min(source.len());
+            let line_start = source[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = source[start..].find('\n').map(|i| start + i).unwrap_or(source.len());
+            let line = source[..start].matches('\n').count() + 1;
+            let column = source[line_start..start].chars().count() + 1;
+            let gutter = " ".repeat(line.to_string().len());
+            let indent: String = source[line_start..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+            let carets = "^".repeat(source[start..end.clamp(start, line_end)].chars().count().max(1));
+            report += &format!("{gutter}--> {path}:{line}:{column}\n");
+            report += &format!("{gutter} |\n{line} | {}\n{gutter} | {indent}{carets}\n", &source[line_start..line_end]);
+            report += &format!("{gutter} = in {}\n\n", chain.join(" > "));
+        }
+        report
+    }
+    /// every error along with where it is
+    pub fn locations(&self) -> Vec<Location<'_>> {
+        let mut errors = Vec::new();
+        self.flatten(None, &mut Vec::new(), &mut errors);
+        errors
+    }
+}
+/// where an error is, see [`ParseErr::locations`]
+#[derive(Debug)]
+pub struct Location<'a> {
+    /// the file it's in if it isn't the one being parsed, see [`ParseErr::in_file`]
+    pub file: Option<&'a str>,
+    /// where it is in the file, if that's known
+    pub span: Option<Range<usize>>,
+    /// the parts of the file it's inside of, outermost first
+    pub chain: Vec<&'a str>,
+    pub err: &'a ParseErr,
+}
+impl Display for ParseErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EarlyCloseBracket => write!(f, "unexpected text after a closing bracket"),
+            Self::NoCloseBracket => write!(f, "bracket is never closed"),
+            Self::NoOpenBracket  => write!(f, "expected an opening bracket"),
+            Self::NotEnoughArgs  => write!(f, "not enough arguments"),
+            Self::ToManyArgs     => write!(f, "too many arguments"),
+            Self::InvalidReqOps  => write!(f, "requirements have to be joined with `&` or `|`"),
+            Self::InvalidPrefix(p)       => write!(f, "unknown prefix `{p}`"),
+            Self::InvalidColliderType(c) => write!(f, "invalid collider type `{c}`"),
+            Self::InvalidNumber(e)       => write!(f, "invalid number, {e}"),
+            Self::InvalidIndex(i)        => write!(f, "invalid whole number `{i}`"),
+            Self::UnsupportedVersion(v)  => write!(f, "unsupported version {v}"),
+            Self::InvalidStat(s)         => write!(f, "unknown stat `{s}`"),
+            Self::InvalidFormula(s)      => write!(f, "invalid formula `{s}`"),
+            Self::DuplicateStat(s)       => write!(f, "stat `{s}` is defined more than once"),
+            Self::InvalidItem(i)         => write!(f, "unknown item `{i}`"),
+            Self::DuplicateItem(i)       => write!(f, "item `{i}` is defined more than once"),
+            Self::InvalidEnemy(e)        => write!(f, "unknown enemy `{e}`"),
+            Self::MissingEncounterStat(s) => write!(f, "encounters need a stat called `{s}`, add it to the stats or remove the enemies"),
+            Self::DuplicateScene(s)      => write!(f, "scene `{s}` is defined more than once"),
+            Self::InvalidPrefab(p)       => write!(f, "unknown prefab `{p}`"),
+            Self::DuplicatePrefab(p)     => write!(f, "prefab `{p}` is defined more than once"),
+            Self::DuplicatePickup(k)     => write!(f, "pickup `{k}` is in the scene more than once, give them different names"),
+            Self::RecursivePrefab(p)     => write!(f, "prefab `{p}` uses itself"),
+            Self::MissingParam(p)        => write!(f, "no value given for `${p}`"),
+            Self::InvalidParam(p)        => write!(f, "the prefab has no `${p}` to fill in"),
+            Self::IoError(e)             => write!(f, "{e}"),
+            Self::InvalidComparison => write!(f, "invalid comparison"),
+            Self::InvalidColour     => write!(f, "invalid colour"),
+            Self::UnclosedQuote     => write!(f, "quote is never closed"),
+            Self::InvalidData(e)    => write!(f, "{e}"),
+            Self::GltfError(e)      => write!(f, "{e}"),
+            Self::Context { err, .. } | Self::InFile { err, .. } => write!(f, "{err}"),
+            Self::Many(errs) => {
+                let errs: Vec<String> = errs.iter().map(ParseErr::to_string).collect();
+                write!(f, "{}", errs.join("\n"))
+            },
+        }
+    }
 }
 impl From<std::io::Error>  for ParseErr { fn from(e: std::io::Error)  -> Self {  Self::IoError(e)       } }
 impl From<ParseFloatError> for ParseErr { fn from(e: ParseFloatError) -> Self {  Self::InvalidNumber(e) } }
+impl From<gltf::Error>     for ParseErr { fn from(e: gltf::Error)     -> Self {  Self::GltfError(e)     } }
+/// how a file is written, chosen by its extension.
+/// anything that isn't `.ron` or `.json` is in the bracket format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Ron,
+    Json,
+}
+impl Format {
+    pub fn of(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("ron")  => Self::Ron,
+            Some("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+    pub fn parse<T: FromStr<Err = ParseErr> + DeserializeOwned>(self, s: &str) -> Result<T, ParseErr> {
+        // line and column from 1, as both crates give them
+        let offset = |line: usize, column: usize| {
+            let start: usize = s.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+            start + s[start..].chars().take(column.saturating_sub(1)).map(char::len_utf8).sum::<usize>()
+        };
+        match self {
+            Self::Text => s.parse(),
+            Self::Ron => ron::from_str(s).map_err(|e| {
+                let (start, end) = (offset(e.span.start.line, e.span.start.col), offset(e.span.end.line, e.span.end.col));
+                ParseErr::InvalidData(e.code.to_string()).context("ron", start..end.max(start + 1))
+            }),
+            Self::Json => serde_json::from_str(s).map_err(|e| {
+                let start = offset(e.line(), e.column());
+                // the position is given separately
+                let message = e.to_string();
+                let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+                ParseErr::InvalidData(message.to_string()).context("json", start..start + 1)
+            }),
+        }
+    }
+    pub fn write<T: Display + Serialize>(self, value: &T) -> Result<String, ParseErr> {
+        match self {
+            Self::Text => Ok(value.to_string()),
+            Self::Ron  => ron::ser::to_string_pretty(value, Default::default())
+                .map_err(|e| ParseErr::InvalidData(e.to_string())),
+            Self::Json => serde_json::to_string_pretty(value).map_err(|e| ParseErr::InvalidData(e.to_string())),
+        }.map(|s| if s.ends_with('\n') { s } else { s + "\n" })
+    }
+}
+/// gives what's inside a bracket that has already been opened and the byte offset just after it closes
 fn split_bracket(s: &str) -> Result<(String, usize), ParseErr> {
     if debug_parse() { println!("splitting bracket: {s}") }
     let mut indent = 1;
-    for (e, c) in s.chars().enumerate() {
-        indent += match c { '[' =>  1, ']' => -1, _ => continue };
-        if indent == 0 { return Ok((s[..e].to_string(), e + 1)) }
-        if indent <  0 { return Err(ParseErr::EarlyCloseBracket) }
+    // scripts use brackets in free text, where a quote may never be closed
+    for token in lexer::tokenize_lenient(s) {
+        indent += match token.kind { TokenKind::Open => 1, TokenKind::Close => -1, _ => continue };
+        if indent == 0 { return Ok((s[..token.span.start].to_string(), token.span.end)) }
     }
     Err(ParseErr::NoCloseBracket)
 }
+/// splits `prefix[inner]` into its trimmed prefix and the inside of its brackets,
+/// an inside that's only a quoted string is given without its quotes
+fn split_call(s: &str) -> Result<(&str, String), ParseErr> {
+    let (prefix, brackets) = s.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
+    let (inner, end) = split_bracket(brackets)?;
+    if !brackets[end..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
+    Ok((prefix.trim(), lexer::unquote(&inner).unwrap_or(inner)))
+}
+/// splits on commas and new lines outside of brackets and quotes, leaving out comments.
+/// an argument that's only a quoted string is given without its quotes
 fn split_args(s: &str) -> Result<Vec<String>, ParseErr> {
+    Ok(split_args_spanned(s)?.into_iter().map(|(arg, _)| arg).collect())
+}
+/// [`split_args`] along with where each argument is in `s`
+fn split_args_spanned(s: &str) -> Result<Vec<(String, Range<usize>)>, ParseErr> {
     if debug_parse() { println!("splitting args: {s}") }
     let mut result = Vec::new();
     let mut current = String::new();
+    let mut span = 0..0;
     let mut indent = 0;
-    let mut control_char = false;
-    for c in s.chars() {
-        if !control_char && c == '[' { indent += 1 }
-        if !control_char && c == ']' { indent -= 1 }
-        if indent < 0 { return Err(ParseErr::EarlyCloseBracket) }
-
-        if indent == 0 && (c == '\n' || c == ',') && !control_char && !current.trim().is_empty()  {
-            result.push(current.trim().to_string());
-            current = String::new();
-        } else {
-            current.push(c);
+    let mut push = |current: &mut String, span: &Range<usize>| if !current.trim().is_empty() {
+        let arg = std::mem::take(current);
+        let start = span.start + arg.len() - arg.trim_start().len();
+        let end = span.end - (arg.len() - arg.trim_end().len());
+        result.push((lexer::unquote(&arg).unwrap_or_else(|| arg.trim().to_string()), start..end));
+    };
+    for token in lexer::tokenize(s)? {
+        match token.kind {
+            TokenKind::Open  => indent += 1,
+            TokenKind::Close => indent -= 1,
+            TokenKind::Separator if indent == 0 => { push(&mut current, &span); continue },
+            _ => (),
+        }
+        if indent < 0 { return Err(ParseErr::EarlyCloseBracket.context("bracket `]`", token.span)) }
+        if current.is_empty() { span.start = token.span.start }
+        span.end = token.span.end;
+        // comments are blanked out rather than left out so spans inside the argument still line up
+        match token.kind {
+            TokenKind::Comment => current.push_str(&" ".repeat(token.span.len())),
+            _ => current.push_str(&s[token.span]),
         }
-        control_char = !control_char && c == '\\';
     }
-    if !current.trim().is_empty() { result.push(current.trim().to_string()) }
+    push(&mut current, &span);
     Ok(result)
 }
+/// what an argument is called in an error, the part before its brackets
+fn describe(arg: &str) -> &str {
+    let name = arg.split('[').next().unwrap_or(arg).trim();
+    if name.is_empty() { arg.trim() } else { name }
+}
+/// parses every argument, carrying on past any that fail so all their errors are given together.
+/// `offset` is where the arguments start in the text the errors should point into
+fn parse_each<T: FromStr<Err = ParseErr>>(
+    args: &[(String, Range<usize>)], offset: usize, kind: &str
+) -> Result<Vec<T>, ParseErr> {
+    let mut parsed = Vec::new();
+    let mut errs = Vec::new();
+    for (arg, span) in args {
+        match arg.parse::<T>() {
+            Ok(t) => parsed.push(t),
+            Err(e) => errs.push(e.context(format!("{kind} `{}`", describe(arg)), span.start + offset..span.end + offset)),
+        }
+    }
+    any_errors(errs).map(|_| parsed)
+}
+/// nothing if there are no errors, otherwise the one error or all of them together
+fn any_errors(mut errs: Vec<ParseErr>) -> Result<(), ParseErr> {
+    match errs.len() {
+        0 => Ok(()),
+        1 => Err(errs.remove(0)),
+        _ => Err(ParseErr::Many(errs)),
+    }
+}
+/// `colour[r, g, b]` from 0 to 255, kept as written
 fn parse_colour(s: &str) -> Result<Vec3, ParseErr> {
     if debug_parse() { println!("parsing colour: {s}") }
     let (prefix, brackets) = s.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
@@ -59,11 +338,7 @@ fn parse_colour(s: &str) -> Result<Vec3, ParseErr> {
     let (inner, end) = split_bracket(brackets)?;
     if !brackets[end..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
     match split_args(&inner)?.as_slice() {
-        [r, g, b] => Ok(vec3(
-            r.parse::<f32>()? / 255.0,
-            g.parse::<f32>()? / 255.0,
-            b.parse::<f32>()? / 255.0
-        )),
+        [r, g, b] => Ok(vec3(r.parse()?, g.parse()?, b.parse()?)),
         [_, _, _, _, ..] => Err(ParseErr::ToManyArgs),
         _ => Err(ParseErr::NotEnoughArgs)
     }
@@ -73,53 +348,49 @@ fn is_true(val: &str)    -> bool { matches!(val.to_lowercase().trim(), "true" |
 pub fn debug_parse()     -> bool { vars().any(|(key, val)| { key == "DEBUG_PARSING"   && is_true(&val) }) }
 pub fn debug_colliders() -> bool { vars().any(|(key, val)| { key == "DEBUG_COLLIDERS" && is_true(&val) }) }
 pub fn debug_lights()    -> bool { vars().any(|(key, val)| { key == "DEBUG_LIGHTS"    && is_true(&val) }) }
+pub fn debug_affinity()  -> bool { vars().any(|(key, val)| { key == "DEBUG_AFFINITY"  && is_true(&val) }) }
 
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CompVal {
-    Recovery,
-        Focus,
-        Reasoning,
-    Fitness,
-        Strength,
-        Speed,
-    Charisma,
-        Expression,
-        Deception,
+    /// index into the [`StatSchema`](stats::StatSchema)
+    Stat(usize),
+    /// how many of an item the player has, from `count[coin]`
+    Count(String),
+    /// how much an NPC likes the player, from `affinity[poca]`
+    Affinity(String),
     Const(f32),
 }
 impl CompVal {
     fn evaluate(&self, data: &PlayerData) -> f32 {
         match self {
-            Self::Recovery   => data.recovery()   as f32,
-            Self::Focus      => data.focus()      as f32,
-            Self::Reasoning  => data.reasoning()  as f32,
-            Self::Fitness    => data.fitness()    as f32,
-            Self::Strength   => data.strength()   as f32,
-            Self::Speed      => data.speed()      as f32,
-            Self::Charisma   => data.charisma()   as f32,
-            Self::Expression => data.expression() as f32,
-            Self::Deception  => data.deception()  as f32,
+            Self::Stat(stat) => data.stat(*stat),
+            Self::Count(item) => data.item_count(item) as f32,
+            Self::Affinity(npc) => data.affinity(npc) as f32,
             Self::Const(v)   => *v,
         }
     }
 }
+/// replaces `self` where an NPC is expected with the name of the object that owns a script
+fn resolve_self(npc: &mut String, owner: &str) { if npc == "self" { *npc = owner.to_string() } }
+impl Display for CompVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stat(stat) => write!(f, "{}", stats::stat_schema().get(*stat).name),
+            Self::Count(item) => write!(f, "count[{item}]"),
+            Self::Affinity(npc) => write!(f, "affinity[{npc}]"),
+            Self::Const(v) => write!(f, "{v}"),
+        }
+    }
+}
 impl FromStr for CompVal {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if debug_parse() { println!("parsing comparison value: {s}") }
-        match s {
-            "recovery" => Ok(Self::Recovery),
-                "focus"     => Ok(Self::Focus),
-                "reasoning" => Ok(Self::Reasoning),
-            "fitness" => Ok(Self::Fitness),
-                "speed" => Ok(Self::Speed),
-                "strength" => Ok(Self::Strength),
-            "charisma" => Ok(Self::Charisma),
-                "expression" => Ok(Self::Expression),
-                "deception" => Ok(Self::Deception),
-            _ => Ok(Self::Const(s.parse()?))
-        }
+        if let Some(stat) = stats::stat_schema().find(s) { return Ok(Self::Stat(stat)) }
+        if let Ok(("count", item)) = split_call(s) { return Ok(Self::Count(items::item_catalogue().check(&item)?)) }
+        if let Ok(("affinity", npc)) = split_call(s) { return Ok(Self::Affinity(npc.trim().to_string())) }
+        s.parse().map(Self::Const).map_err(|_| ParseErr::InvalidStat(s.to_string()))
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -146,6 +417,18 @@ impl FromStr for Comparison {
         }
     }
 }
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::LessThan           => "<",
+            Self::LessThanOrEqual    => "<=",
+            Self::GreaterThan        => ">",
+            Self::GreaterThanOrEqual => ">=",
+            Self::Equal              => "=",
+            Self::NotEqual           => "!=",
+        })
+    }
+}
 impl Comparison {
     pub fn evaluate(self, v1: &CompVal, v2: &CompVal, data: &PlayerData) -> bool {
         match self {
@@ -163,6 +446,8 @@ pub enum ReqVal {
     Not(Box<ReqVal>),
     Req(Box<Requirements>),
     Tag(String),
+    /// the player has at least one of an item, from `has[key]`
+    Has(String),
     Comparison(CompVal, Comparison, CompVal),
 }
 impl ReqVal {
@@ -171,28 +456,57 @@ impl ReqVal {
             Self::Not(i) => !i.evaluate(data),
             Self::Req(i) => i.evaluate(data),
             Self::Tag(s) => data.acquired_tags.contains(s),
+            Self::Has(item) => data.item_count(item) > 0,
             Self::Comparison(v1, c, v2) => c.evaluate(v1, v2, data),
         }
     }
+    fn resolve_self(&mut self, owner: &str) {
+        match self {
+            Self::Not(i) => i.resolve_self(owner),
+            Self::Req(i) => i.resolve_self(owner),
+            Self::Comparison(v1, _, v2) => for v in [v1, v2] {
+                if let CompVal::Affinity(npc) = v { resolve_self(npc, owner) }
+            },
+            Self::Tag(_) | Self::Has(_) => (),
+        }
+    }
+    fn tags<'a>(&'a self, tags: &mut Vec<&'a str>) {
+        match self {
+            Self::Not(i) => i.tags(tags),
+            Self::Req(i) => tags.append(&mut i.tags()),
+            Self::Tag(tag) => tags.push(tag),
+            Self::Has(_) | Self::Comparison(..) => (),
+        }
+    }
+}
+impl Display for ReqVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Not(i) => write!(f, "!{i}"),
+            Self::Req(i) => write!(f, "[{i}]"),
+            Self::Tag(s) => write!(f, "{s}"),
+            Self::Has(item) => write!(f, "has[{item}]"),
+            Self::Comparison(v1, c, v2) => write!(f, "{v1} {c} {v2}"),
+        }
+    }
 }
 impl FromStr for ReqVal {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if debug_parse() { println!("parsing requirement value: {s}") }
-        match &s[0..1] {
-            "[" => {
-                let (req, skip) = split_bracket(&s[1..])?;
-                if !s[(skip+1)..].trim().is_empty() { todo!() }
-                Ok(Self::Req(Box::new(req.parse()?)))
-            },
-            "!" => Ok(Self::Not(Box::new(s[1..].parse()?))),
-             _  => {
-                if let Ok((s, c, e)) = split_comparison(s) {
-                    Ok(Self::Comparison(s.parse()?, c.parse()?, e.parse()?))
-                } else {
-                    Ok(Self::Tag(s.trim().to_string()))
-                }
-             }
+        if s.trim().is_empty() { return Err(ParseErr::NotEnoughArgs) }
+        if let Some(inner) = s.strip_prefix('[') {
+            let (req, skip) = split_bracket(inner)?;
+            if !inner[skip..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
+            Ok(Self::Req(Box::new(req.parse()?)))
+        } else if let Some(inner) = s.strip_prefix('!') {
+            Ok(Self::Not(Box::new(inner.parse()?)))
+        } else if let Ok((s, c, e)) = split_comparison(s) {
+            Ok(Self::Comparison(s.parse()?, c.parse()?, e.parse()?))
+        } else if let Ok(("has", item)) = split_call(s) {
+            Ok(Self::Has(items::item_catalogue().check(&item)?))
+        } else {
+            Ok(Self::Tag(s.trim().to_string()))
         }
     }
 }
@@ -216,7 +530,9 @@ pub enum ReqOps {
     And,
     Or,
 }
-#[derive(Debug, PartialEq, Clone)]
+/// written the same as in the bracket format when in `.ron` or `.json`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct Requirements {
     head: ReqVal,
     body: Vec<(ReqOps, ReqVal)>
@@ -232,36 +548,71 @@ impl Requirements {
         }
         result
     }
+    /// see [`Script::with_owner`](script::Script::with_owner)
+    pub fn resolve_self(&mut self, owner: &str) {
+        self.head.resolve_self(owner);
+        for (_, val) in &mut self.body { val.resolve_self(owner) }
+    }
+    /// every tag the requirements check for
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags = Vec::new();
+        self.head.tags(&mut tags);
+        for (_, val) in &self.body { val.tags(&mut tags) }
+        tags
+    }
+}
+impl Display for Requirements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.head)?;
+        for (ops, val) in &self.body {
+            match ops {
+                ReqOps::And => write!(f, " & {val}")?,
+                ReqOps::Or  => write!(f, " | {val}")?,
+            }
+        }
+        Ok(())
+    }
+}
+impl TryFrom<String> for Requirements {
+    type Error = ParseErr;
+    fn try_from(s: String) -> Result<Self, Self::Error> { s.parse() }
+}
+impl From<Requirements> for String {
+    fn from(requirements: Requirements) -> Self { requirements.to_string() }
 }
 impl FromStr for Requirements {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if debug_parse() { println!("parsing requirements: {s}") }
         let s = s.replace(char::is_whitespace, "");
-        let (head, s) = s.split_at(s.find(['&', '|']).unwrap_or(s.len()));
-        let mut ops = None;
-
-        let head = head.parse()?;
+        let mut head = None;
         let mut body = Vec::new();
-        let mut last = 1;
-        for (i, c) in s.chars().enumerate() {
-            match c {
-                '&' => {
-                    if let Some(ops) = ops { body.push((ops, s[last..i].parse()?))}
-                    last = i + 1;
-                    ops = Some(ReqOps::And);
-                },
-                '|' => {
-                    if let Some(ops) = ops { body.push((ops, s[last..i].parse()?)) }
-                    last = i + 1;
-                    ops = Some(ReqOps::Or);
-
-                },
-                _ => if ops.is_none() { return Err(ParseErr::InvalidReqOps) }
+        let mut ops = None;
+        let mut indent = 0;
+        let mut last = 0;
+        // only `&` and `|` outside of brackets join requirements, the ones inside belong to a group
+        for (i, c) in s.char_indices() {
+            let next = match c {
+                '[' => { indent += 1; continue },
+                ']' => { indent -= 1; continue },
+                '&' if indent == 0 => ReqOps::And,
+                '|' if indent == 0 => ReqOps::Or,
+                _ => continue,
+            };
+            if s[last..i].is_empty() { return Err(ParseErr::InvalidReqOps) }
+            let val = s[last..i].parse()?;
+            match ops {
+                Some(ops) => body.push((ops, val)),
+                None => head = Some(val),
             }
+            ops = Some(next);
+            last = i + 1;
         }
-
-        if let Some(ops) = ops { body.push((ops, s[last..].parse()?)) }
-        Ok(Self { head, body })
+        let val = s[last..].parse()?;
+        match ops {
+            Some(ops) => body.push((ops, val)),
+            None => head = Some(val),
+        }
+        Ok(Self { head: head.ok_or(ParseErr::NotEnoughArgs)?, body })
     }
 }
@@ -0,0 +1,158 @@
+use std::str::FromStr;
+use crate::{file_types::*, PlayerData};
+
+pub const QUESTS_PATH: &str = "quests.qst";
+
+/// every quest in the game. a quest starts once its first stage's `start` is met and moves
+/// to the next stage once the current stage is `done` and the next one's `start` is met.
+/// a stage without a `start` can always be started, and `xp` is given when the quest is complete.
+/// ```text
+/// quest#pocket_change[
+///     title[Pocket Change]
+///     xp[charisma 10]
+///     stage[start[has[coin]], done[count[coin] >= 2], description[Find another coin]]
+///     stage[done[paid], description[Give the coins to Poca]]
+/// ]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct QuestLog {
+    pub quests: Vec<Quest>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub stages: Vec<Stage>,
+    pub xp: Vec<(usize, u32)>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub description: String,
+    pub start: Option<Requirements>,
+    pub done: Requirements,
+}
+impl Stage {
+    fn can_start(&self, data: &PlayerData) -> bool { self.start.as_ref().is_none_or(|r| r.evaluate(data)) }
+}
+/// how far the player is through a quest, `None` if it hasn't started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestState {
+    Stage(usize),
+    Complete,
+}
+impl Quest {
+    pub fn state(&self, data: &PlayerData) -> Option<QuestState> {
+        let stage = data.quest_stage(&self.id)?;
+        if stage >= self.stages.len() { Some(QuestState::Complete) } else { Some(QuestState::Stage(stage)) }
+    }
+}
+impl QuestLog {
+    /// a missing quest file just means there are no quests
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+    /// moves every quest on as far as the player's progress allows,
+    /// giving a notification for each quest started, advanced or completed and each stat it raised
+    pub fn update(&self, data: &mut PlayerData) -> Vec<String> {
+        let mut notifications = Vec::new();
+        for quest in &self.quests {
+            let Some(first) = quest.stages.first() else { continue };
+            let mut stage = match data.quest_stage(&quest.id) {
+                Some(stage) => stage,
+                None if first.can_start(data) => {
+                    notifications.push(format!("New quest: {}", quest.title));
+                    0
+                },
+                None => continue,
+            };
+            while let Some(current) = quest.stages.get(stage) {
+                if !current.done.evaluate(data) { break }
+                match quest.stages.get(stage + 1) {
+                    Some(next) if !next.can_start(data) => break,
+                    Some(next) => notifications.push(format!("{}: {}", quest.title, next.description)),
+                    None => {
+                        notifications.push(format!("Quest complete: {}", quest.title));
+                        for (stat, amount) in &quest.xp {
+                            if data.gain_xp(*stat, *amount) > 0 { notifications.push(data.level_up_message(*stat)) }
+                        }
+                    },
+                }
+                stage += 1;
+            }
+            data.set_quest_stage(&quest.id, stage);
+        }
+        notifications
+    }
+}
+impl FromStr for QuestLog {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing quests: {s}") }
+        let quests = parse_each(&split_args_spanned(s)?, 0, "quest")?;
+        Ok(Self { quests })
+    }
+}
+impl FromStr for Quest {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing quest: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        let (prefix, id) = prefix.split_once('#').ok_or(ParseErr::NotEnoughArgs)?;
+        if prefix != "quest" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let mut quest = Quest {
+            id: id.trim().to_string(), title: id.trim().to_string(), stages: Vec::new(), xp: Vec::new()
+        };
+        for arg in split_args(&inner)? {
+            match split_call(&arg)? {
+                ("title", title) => quest.title = title.trim().to_string(),
+                ("stage", stage) => quest.stages.push(stage.parse()?),
+                ("xp", xp) => quest.xp.append(&mut script::parse_xp_gains(&xp)?),
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(quest)
+    }
+}
+impl FromStr for Stage {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing quest stage: {s}") }
+        let mut description = String::new();
+        let mut start = None;
+        let mut done  = None;
+        for arg in split_args(s)? {
+            match split_call(&arg)? {
+                ("description", d) => description = d.trim().to_string(),
+                ("start", r) => start = Some(r.parse()?),
+                ("done",  r) => done  = Some(r.parse()?),
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(Self { description, start, done: done.ok_or(ParseErr::NotEnoughArgs)? })
+    }
+}
+
+#[test]
+fn quest_stages_advance() {
+    let log: QuestLog = "quest#errand[
+        title[Errand]
+        stage[start[asked], done[found], description[Find it]]
+        stage[start[told], done[returned], description[Bring it back]]
+    ]".parse().unwrap();
+    let mut data = PlayerData::default();
+    assert!(log.update(&mut data).is_empty());
+    assert_eq!(log.quests[0].state(&data), None);
+
+    data.acquired_tags = vec!["asked".to_string(), "found".to_string()];
+    assert_eq!(log.update(&mut data).len(), 1);
+    // found but the next stage can't start yet
+    assert_eq!(log.quests[0].state(&data), Some(QuestState::Stage(0)));
+
+    data.acquired_tags.extend(["told".to_string(), "returned".to_string()]);
+    assert_eq!(log.update(&mut data), ["Errand: Bring it back", "Quest complete: Errand"]);
+    assert_eq!(log.quests[0].state(&data), Some(QuestState::Complete));
+}
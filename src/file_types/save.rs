@@ -0,0 +1,222 @@
+use std::{str::FromStr, fmt::Display, path::PathBuf};
+use crate::{file_types::{*, script::ReaderState}, PlayerData};
+
+/// bumped whenever the layout of a save file changes.
+/// version 1 saves have no content version and are treated as content version 0.
+/// version 2 saves split stats into `stats` and sub-stat `modifiers`, both are read as stats
+pub const SAVE_VERSION: u32 = 3;
+pub const SAVE_DIR: &str = "saves";
+pub const MANUAL_SLOTS: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveSlot {
+    /// written every time the player goes through an exit
+    Auto,
+    Manual(u8),
+}
+impl SaveSlot {
+    pub fn path(self) -> PathBuf {
+        match self {
+            Self::Auto      => PathBuf::from(SAVE_DIR).join("autosave.sav"),
+            Self::Manual(i) => PathBuf::from(SAVE_DIR).join(format!("slot_{i}.sav")),
+        }
+    }
+    /// cycles through the autosave and then each manual slot
+    pub fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Manual(1),
+            Self::Manual(i) if i >= MANUAL_SLOTS => Self::Auto,
+            Self::Manual(i) => Self::Manual(i + 1),
+        }
+    }
+}
+impl Display for SaveSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto      => write!(f, "Autosave"),
+            Self::Manual(i) => write!(f, "Slot {i}"),
+        }
+    }
+}
+/// everything needed to put the player back where they were.
+/// ```text
+/// save[
+///     version[3]
+///     content[0]
+///     scene[waiting_room]
+///     name[Sam]
+///     pos[0, 0, 0]
+///     facing[0, 0, 1]
+///     stats[recovery[1], focus[0], reasoning[0], fitness[1], ...]
+///     xp[fitness[12]]
+///     tags[ok, yay]
+///     read[test.scr]
+///     items[key[1], coin[4]]
+///     picked[waiting_room#pickup@2:0:1]
+///     quests[pocket_change[1]]
+///     affinity[poca[2]]
+///     reader[test.scr, 2, options[0], owner[poca]]
+/// ]
+/// ```
+#[derive(Debug, Clone)]
+pub struct SaveData {
+    pub version: u32,
+    /// the latest migration this save has had applied, see [`Migrations`](super::migration::Migrations)
+    pub content_version: u32,
+    pub scene: String,
+    pub player: PlayerData,
+    pub dialogue: Option<ReaderState>,
+    /// stats in the save that aren't in the current [`StatSchema`](super::stats::StatSchema)
+    pub unknown_stats: Vec<(String, i32)>,
+}
+impl SaveData {
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
+        std::io::read_to_string(std::fs::File::open(path)?)?.parse()
+    }
+    pub fn write_to_slot(&self, slot: SaveSlot) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(SAVE_DIR)?;
+        std::fs::write(slot.path(), self.to_string())
+    }
+}
+impl Display for SaveData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let PlayerData { name, stats, xp, acquired_tags, read_scripts, inventory, picked_up, quests, affinity, pos, facing } = &self.player;
+        let schema = stats::stat_schema();
+        let quests: Vec<String> = quests.iter().map(|(quest, stage)| format!("{quest}[{stage}]")).collect();
+        let xp: Vec<String> = xp.iter().enumerate().filter(|(_, xp)| **xp > 0)
+            .map(|(i, xp)| format!("{}[{xp}]", schema.get(i).name)).collect();
+        let affinity: Vec<String> = affinity.iter().map(|(npc, v)| format!("{npc}[{v}]")).collect();
+        let inventory: Vec<String> = inventory.iter().map(|(item, n)| format!("{item}[{n}]")).collect();
+        let stats: Vec<String> = stats.iter().enumerate()
+            .map(|(i, v)| format!("{}[{v}]", schema.get(i).name))
+            .chain(self.unknown_stats.iter().map(|(name, v)| format!("{name}[{v}]")))
+            .collect();
+        writeln!(f, "save[")?;
+        writeln!(f, "    version[{}]", self.version)?;
+        writeln!(f, "    content[{}]", self.content_version)?;
+        writeln!(f, "    scene[{}]", self.scene)?;
+        writeln!(f, "    name[{name}]")?;
+        writeln!(f, "    pos[{}, {}, {}]", pos.x, pos.y, pos.z)?;
+        writeln!(f, "    facing[{}, {}, {}]", facing.x, facing.y, facing.z)?;
+        writeln!(f, "    stats[{}]", stats.join(", "))?;
+        writeln!(f, "    xp[{}]", xp.join(", "))?;
+        writeln!(f, "    tags[{}]", acquired_tags.join(", "))?;
+        writeln!(f, "    read[{}]", read_scripts.join(", "))?;
+        writeln!(f, "    items[{}]", inventory.join(", "))?;
+        writeln!(f, "    picked[{}]", picked_up.join(", "))?;
+        writeln!(f, "    quests[{}]", quests.join(", "))?;
+        writeln!(f, "    affinity[{}]", affinity.join(", "))?;
+        if let Some(dialogue) = &self.dialogue { writeln!(f, "    {dialogue}")? }
+        write!(f, "]")
+    }
+}
+impl FromStr for SaveData {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing save: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        if prefix != "save" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let mut version = None;
+        let mut content_version = 0;
+        let mut scene   = None;
+        let mut dialogue = None;
+        let mut unknown_stats = Vec::new();
+        let mut player = PlayerData::default();
+        for arg in split_args(&inner)? {
+            if arg.starts_with("reader") { dialogue = Some(arg.parse()?); continue }
+            let (prefix, inner) = split_call(&arg)?;
+            match prefix {
+                "version" => {
+                    let v = inner.trim().parse().map_err(|_| ParseErr::InvalidIndex(inner.clone()))?;
+                    if v > SAVE_VERSION { return Err(ParseErr::UnsupportedVersion(v)) }
+                    version = Some(v);
+                },
+                "content" => content_version = inner.trim().parse()
+                    .map_err(|_| ParseErr::InvalidIndex(inner.clone()))?,
+                "scene"  => scene = Some(inner.trim().to_string()),
+                "name"   => player.name = inner.trim().to_string(),
+                "pos"    => player.pos    = parse_vec3(&inner)?,
+                "facing" => player.facing = parse_vec3(&inner)?,
+                "stats" | "modifiers" => for stat in split_args(&inner)? {
+                    let (name, value) = split_call(&stat)?;
+                    let value = value.trim().parse().map_err(|_| ParseErr::InvalidIndex(value.clone()))?;
+                    match stats::stat_schema().find(name) {
+                        Some(stat) => player.stats[stat] = value,
+                        None => unknown_stats.push((name.to_string(), value)),
+                    }
+                },
+                // experience for stats that no longer exist is dropped
+                "xp" => for stat in split_args(&inner)? {
+                    let (name, value) = split_call(&stat)?;
+                    let value = value.trim().parse().map_err(|_| ParseErr::InvalidIndex(value.clone()))?;
+                    if let Some(stat) = stats::stat_schema().find(name) { player.xp[stat] = value }
+                },
+                "tags" => player.acquired_tags = split_args(&inner)?,
+                "read" => player.read_scripts  = split_args(&inner)?,
+                "picked" => player.picked_up = split_args(&inner)?,
+                "affinity" => for npc in split_args(&inner)? {
+                    let (npc, value) = split_call(&npc)?;
+                    let value = value.trim().parse().map_err(|_| ParseErr::InvalidIndex(value.clone()))?;
+                    player.affinity.push((npc.to_string(), value));
+                },
+                "quests" => for quest in split_args(&inner)? {
+                    let (quest, stage) = split_call(&quest)?;
+                    let stage = stage.trim().parse().map_err(|_| ParseErr::InvalidIndex(stage.clone()))?;
+                    player.quests.push((quest.to_string(), stage));
+                },
+                "items" => for item in split_args(&inner)? {
+                    let (item, count) = split_call(&item)?;
+                    let count = count.trim().parse().map_err(|_| ParseErr::InvalidIndex(count.clone()))?;
+                    player.inventory.push((item.to_string(), count));
+                },
+                prefix => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            }
+        }
+        Ok(Self {
+            version: version.ok_or(ParseErr::NotEnoughArgs)?,
+            content_version,
+            scene:   scene  .ok_or(ParseErr::NotEnoughArgs)?,
+            player, dialogue, unknown_stats,
+        })
+    }
+}
+fn parse_vec3(s: &str) -> Result<Vec3, ParseErr> {
+    match split_args(s)?.as_slice() {
+        [x, y, z] => Ok(vec3(x.parse()?, y.parse()?, z.parse()?)),
+        [_, _, _, _, ..] => Err(ParseErr::ToManyArgs),
+        _ => Err(ParseErr::NotEnoughArgs),
+    }
+}
+
+#[test]
+fn save_round_trip() {
+    let mut save = SaveData {
+        version: SAVE_VERSION,
+        content_version: 3,
+        scene: "waiting_room".to_string(),
+        player: PlayerData {
+            name: "Sam".to_string(),
+            acquired_tags: vec!["ok".to_string(), "yay".to_string()],
+            read_scripts: vec!["test.scr".to_string()],
+            inventory: vec![("coin".to_string(), 4)],
+            picked_up: vec!["waiting_room#pickup@2:0:1".to_string()],
+            quests: vec![("pocket_change".to_string(), 1)],
+            affinity: vec![("poca".to_string(), -1)],
+            pos: vec3(1.5, -2.0, 0.25), facing: vec3(0.0, 0.0, -1.0),
+            ..PlayerData::default()
+        },
+        dialogue: Some(ReaderState {
+            script_path: "test.scr".to_string(), owner: "poca".to_string(), index: 1, option_index: vec![0]
+        }),
+        unknown_stats: vec![("luck".to_string(), 4)],
+    };
+    let deception = stats::stat_schema().find("deception").unwrap();
+    save.player.change_stat(deception, 2);
+    save.player.gain_xp(deception, 5);
+    let parsed: SaveData = save.to_string().parse().unwrap();
+    assert_eq!(parsed.content_version, save.content_version);
+    assert_eq!(parsed.scene, save.scene);
+    assert_eq!(parsed.player, save.player);
+    assert_eq!(parsed.dialogue, save.dialogue);
+    assert_eq!(parsed.unknown_stats, save.unknown_stats);
+}
@@ -1,13 +1,195 @@
 use crate::{graphics::DrawInfo, file_types::*, collision::ColliderType};
 use thin_engine::prelude::*;
-use std::{str::FromStr, f32::consts::TAU, collections::HashMap};
+use std::{str::FromStr, fmt::Display, f32::consts::TAU, collections::HashMap, ops::Range, path::{Path, PathBuf}};
 use ObjectType::*;
-#[derive(Debug)]
+/// the scenes played when no other scene file or directory is given
+pub const SCENES_PATH: &str = "test.scn";
+
+/// every scene in the game. scenes can be split over several files,
+/// either all the scene files in a directory or files brought in with `include[path]`
+/// where the path is relative to the file it's written in.
+/// any file can define [prefabs](Prefab) for every other file to use.
+/// `.ron` and `.json` files hold the same things, see [`Format`]
+/// ```text
+/// include[rooms/hallway.scn]
+/// #waiting_room[
+///     camera[pos[0, 0, 10]]
+///     exit#door[hallway, door, trigger[cube]]
+/// ]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ScenesFields", into = "ScenesFields")]
 pub struct GameScenes {
     scenes: Vec<Scene>,
     index: HashMap<String, usize>,
+    prefabs: Vec<Prefab>,
+    /// paths from `include[path]` and where they're written. parsing leaves them to be loaded by
+    /// [`load`](Self::load) so the scenes can still be printed back the way they were written
+    includes: Vec<(String, Range<usize>)>,
+}
+/// how [`GameScenes`] are written in `.ron` and `.json`
+#[derive(Serialize, Deserialize)]
+struct ScenesFields {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    includes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prefabs: Vec<Prefab>,
+    scenes: Vec<Scene>,
+}
+impl TryFrom<ScenesFields> for GameScenes {
+    type Error = ParseErr;
+    fn try_from(fields: ScenesFields) -> Result<Self, Self::Error> {
+        let includes = fields.includes.into_iter().map(|path| (path, 0..0)).collect();
+        GameScenes::new(fields.scenes, &[], fields.prefabs, &[], includes)
+    }
+}
+impl From<GameScenes> for ScenesFields {
+    fn from(scenes: GameScenes) -> Self {
+        let includes = scenes.includes.into_iter().map(|(path, _)| path).collect();
+        Self { includes, prefabs: scenes.prefabs, scenes: scenes.scenes }
+    }
+}
+/// a scene file as [`GameScenes::load`] read it
+struct SceneFile {
+    path: PathBuf,
+    source: String,
+    scenes: GameScenes,
+}
+impl GameScenes {
+    /// loads a scene file, or every `.scn`, `.ron`, `.json` and glTF file in a directory, along with the files they include
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParseErr> { Self::load_tracked(path, &mut Vec::new()) }
+    /// [`load`](Self::load), filling `read` with the files it read even if they don't parse
+    pub fn load_tracked(path: impl AsRef<Path>, read: &mut Vec<PathBuf>) -> Result<Self, ParseErr> {
+        let path = path.as_ref();
+        let paths = if path.is_dir() {
+            let mut paths: Vec<PathBuf> = std::fs::read_dir(path)?.filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "scn" || e == "ron" || e == "json") || gltf_import::is_gltf(p))
+                .collect();
+            paths.sort();
+            paths
+        } else { vec![path.to_path_buf()] };
+        let mut files = Vec::new();
+        let mut errs = Vec::new();
+        for path in paths {
+            read.push(path.clone());
+            let result = read_source(&path).map_err(ParseErr::from)
+                .and_then(|source| Self::read_file(&path, source, &mut files));
+            if let Err(e) = result { errs.push(e.in_file(path.display().to_string())) }
+        }
+        read.extend(files.iter().map(|f| f.path.clone()));
+        // a prefab can be used by any file, so they're all gathered before any are filled in
+        let mut scenes = Self::default();
+        let mut spans = Vec::new();
+        for file in &mut files {
+            // only the bracket format knows where things are
+            let [_, prefab_args, scene_args] = match Format::of(&file.path) {
+                Format::Text => split_file(&file.source)?,
+                _ => Default::default(),
+            };
+            let span = |name: &str| prefab_args.iter().find(|(arg, _)| describe(arg) == name).map(|(_, span)| span.clone());
+            for prefab in std::mem::take(&mut file.scenes.prefabs) {
+                if !scenes.prefabs.iter().any(|p| p.name == prefab.name) { scenes.prefabs.push(prefab); continue }
+                let name = format!("prefab#{}", prefab.name);
+                let err = ParseErr::DuplicatePrefab(prefab.name).context(format!("prefab `{name}`"), span(&name).unwrap_or(0..0));
+                errs.push(err.in_file(file.path.display().to_string()));
+            }
+            spans.push(scene_args);
+        }
+        for (file, args) in files.into_iter().zip(spans) {
+            let in_file = |e: ParseErr| e.in_file(file.path.display().to_string());
+            let mut file_scenes = file.scenes.scenes;
+            if let Err(e) = expand_prefabs(&mut file_scenes, &scenes.prefabs, true, &args) { errs.push(in_file(e)) }
+            for scene in file_scenes {
+                if !scenes.index.contains_key(&scene.name) {
+                    scenes.index.insert(scene.name.clone(), scenes.scenes.len());
+                    scenes.scenes.push(scene);
+                    continue
+                }
+                let name = format!("#{}", scene.name);
+                let span = args.iter().find(|(arg, _)| describe(arg) == name).map(|(_, span)| span.clone()).unwrap_or(0..0);
+                errs.push(in_file(ParseErr::DuplicateScene(scene.name).context(format!("scene `{name}`"), span)));
+            }
+        }
+        any_errors(errs).map(|_| scenes)
+    }
+    /// reads a file and the files it includes, each file is only read once.
+    /// errors in other files are given [in that file](ParseErr::in_file)
+    fn read_file(path: &Path, source: String, files: &mut Vec<SceneFile>) -> Result<(), ParseErr> {
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        if files.iter().any(|f| canonical(&f.path) == canonical(path)) { return Ok(()) }
+        let scenes = if gltf_import::is_gltf(path) { gltf_import::import_scenes(path)? }
+            else { Format::of(path).parse(&source)? };
+        let includes = scenes.includes.clone();
+        files.push(SceneFile { path: path.to_path_buf(), source, scenes });
+        let mut errs = Vec::new();
+        for (include, span) in includes {
+            let included = path.parent().unwrap_or(Path::new("")).join(&include);
+            let result = match read_source(&included) {
+                Ok(source) => Self::read_file(&included, source, files)
+                    .map_err(|e| e.in_file(included.display().to_string())),
+                Err(e) => Err(ParseErr::from(e).context(format!("include `{include}`"), span)),
+            };
+            if let Err(e) = result { errs.push(e) }
+        }
+        any_errors(errs)
+    }
+    /// prefabs are filled in where they can be and names are checked to be unique,
+    /// the scenes' text and the spans are where each scene and prefab is in its file if that's known
+    fn new(
+        mut scenes: Vec<Scene>, scene_args: &[(String, Range<usize>)],
+        prefabs: Vec<Prefab>, prefab_spans: &[Range<usize>],
+        includes: Vec<(String, Range<usize>)>,
+    ) -> Result<Self, ParseErr> {
+        let span = |spans: &[Range<usize>], i: usize| spans.get(i).cloned().unwrap_or(0..0);
+        let mut errs = Vec::new();
+        for (i, prefab) in prefabs.iter().enumerate() {
+            if prefabs[..i].iter().any(|p| p.name == prefab.name) {
+                errs.push(ParseErr::DuplicatePrefab(prefab.name.clone()).context(format!("prefab `prefab#{}`", prefab.name), span(prefab_spans, i)));
+            }
+        }
+        any_errors(errs)?;
+        let scene_spans: Vec<Range<usize>> = scene_args.iter().map(|(_, span)| span.clone()).collect();
+        let spans: Vec<Range<usize>> = (0..scenes.len()).map(|i| span(&scene_spans, i)).collect();
+        // prefabs from other files are filled in by `load`
+        expand_prefabs(&mut scenes, &prefabs, false, scene_args)?;
+        let mut index = HashMap::new();
+        let mut errs = Vec::new();
+        for (i, (scene, span)) in scenes.iter().zip(spans).enumerate() {
+            if index.insert(scene.name.clone(), i).is_some() {
+                errs.push(ParseErr::DuplicateScene(scene.name.clone()).context(format!("scene `#{}`", scene.name), span));
+            }
+        }
+        any_errors(errs)?;
+        Ok(Self { scenes, index, prefabs, includes })
+    }
+    /// scenes made some other way, like [imported from glTF](gltf_import::import_scenes)
+    pub fn from_scenes(scenes: Vec<Scene>) -> Result<Self, ParseErr> {
+        Self::new(scenes, &[], Vec::new(), &[], Vec::new())
+    }
+    pub fn get_index(&self, s: &str) -> usize { self.index[s] }
+    pub fn find(&self, s: &str) -> Option<usize> { self.index.get(s).copied() }
+    pub fn iter(&self) -> impl Iterator<Item = &Scene> { self.scenes.iter() }
+    /// problems with exits that would otherwise only show up when walking through them,
+    /// exits to scenes that don't exist and exits with no exit of the same name to arrive at
+    pub fn check_exits(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for scene in &self.scenes {
+            for (o, _) in scene.all_objects() {
+                let Exit { exit_scene, exit_name, .. } = &o.object_type else { continue };
+                let Some(target) = self.find(exit_scene) else {
+                    problems.push(format!("exit `{}` in scene `{}` leads to scene `{exit_scene}` which doesn't exist", o.name, scene.name));
+                    continue
+                };
+                let arrives = self[target].all_objects().iter()
+                    .any(|(o, _)| o.name == *exit_name && matches!(o.object_type, Exit { .. }));
+                if !arrives { problems.push(format!(
+                    "exit `{}` in scene `{}` arrives at exit `{exit_name}` which scene `{exit_scene}` doesn't have", o.name, scene.name
+                )) }
+            }
+        }
+        problems
+    }
 }
-impl GameScenes { pub fn get_index(&self, s: &str) -> usize { self.index[s] } }
 impl std::ops::Index<usize> for GameScenes {
     type Output = Scene;
     fn index(&self, index: usize) -> &Self::Output { &self.scenes[index] }
@@ -20,27 +202,173 @@ impl FromStr for GameScenes {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if debug_parse() { println!("parsing scenes: {s}") }
-        let mut index = HashMap::new();
-        let mut scenes = Vec::new();
-        for (i, arg) in split_args(s)?.iter().enumerate() {
-            let scene: Scene = arg.parse()?;
-            index.insert(scene.name.clone(), i);
-            scenes.push(scene);
+        let [includes, prefabs, args] = split_file(s)?;
+        let includes = includes.into_iter().map(|(arg, span)| match split_call(&arg) {
+            Ok((_, path)) => Ok((path.trim().to_string(), span)),
+            Err(e) => Err(e.context("include", span)),
+        }).collect::<Result<_, _>>()?;
+        let prefab_spans: Vec<Range<usize>> = prefabs.iter().map(|(_, span)| span.clone()).collect();
+        let prefabs = parse_each(&prefabs, 0, "prefab")?;
+        let scenes = parse_each(&args, 0, "scene")?;
+        Self::new(scenes, &args, prefabs, &prefab_spans, includes)
+    }
+}
+impl Display for GameScenes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (include, _) in &self.includes { writeln!(f, "include[{}]", lexer::quote(include))? }
+        let blocks = self.prefabs.iter().map(ToString::to_string).chain(self.scenes.iter().map(ToString::to_string));
+        for (i, block) in blocks.enumerate() {
+            if i > 0 || !self.includes.is_empty() { writeln!(f)? }
+            writeln!(f, "{block}")?;
+        }
+        Ok(())
+    }
+}
+/// the text of a scene file, glTF files are read by the [importer](gltf_import) instead
+fn read_source(path: &Path) -> std::io::Result<String> {
+    if gltf_import::is_gltf(path) { std::fs::metadata(path).map(|_| String::new()) } else { std::fs::read_to_string(path) }
+}
+/// arguments along with where they are, see [`split_args_spanned`]
+type Spanned = Vec<(String, Range<usize>)>;
+/// the arguments of a scene file split into its includes, prefabs and scenes
+fn split_file(s: &str) -> Result<[Spanned; 3], ParseErr> {
+    let mut parts = [Vec::new(), Vec::new(), Vec::new()];
+    for (arg, span) in split_args_spanned(s)? {
+        let part = match describe(&arg).split('#').next() {
+            Some("include") => 0,
+            Some("prefab")  => 1,
+            _ => 2,
+        };
+        parts[part].push((arg, span));
+    }
+    Ok(parts)
+}
+/// fills in every `use` in `scenes`, `args` being the text of each scene and where it is in its file.
+/// prefabs that can't be found are left for later unless `strict`
+fn expand_prefabs(scenes: &mut [Scene], prefabs: &[Prefab], strict: bool, args: &[(String, Range<usize>)]) -> Result<(), ParseErr> {
+    let mut errs = Vec::new();
+    for (i, scene) in scenes.iter_mut().enumerate() {
+        let (text, span) = args.get(i).map_or(("", 0..0), |(text, span)| (text.as_str(), span.clone()));
+        let object_spans = object_spans(text);
+        for (j, o) in scene.objects.iter_mut().enumerate() {
+            if let Err(e) = o.expand(prefabs, strict, &mut Vec::new(), object_spans.get(j).cloned().unwrap_or(0..0)) {
+                errs.push(e.context(format!("scene `#{}`", scene.name), span.clone()));
+            }
+        }
+        scene.assign_pickup_keys();
+        if let Some(key) = scene.duplicate_pickup_key() {
+            errs.push(ParseErr::DuplicatePickup(key.to_string()).context(format!("scene `#{}`", scene.name), span.clone()));
         }
-        Ok(Self { scenes, index })
     }
+    any_errors(errs)
+}
+/// where each object is in the text of a scene, the same way [`Scene::from_str`] gives them
+fn object_spans(scene: &str) -> Vec<Range<usize>> {
+    let Some((_, brackets)) = scene.split_once('[') else { return Vec::new() };
+    let offset = scene.len() - brackets.len();
+    let Ok((args, _)) = split_bracket(brackets) else { return Vec::new() };
+    // the first argument is the camera
+    split_args_spanned(&args).unwrap_or_default().into_iter().skip(1)
+        .map(|(_, span)| span.start + offset..span.end + offset)
+        .collect()
 }
-#[derive(Debug)]
+/// objects that can be placed in any scene with `use#name[prefab, pos[..], rot[..], scale[..]]`.
+/// an argument written as `$param` is filled in by each `use` giving `param[value]`,
+/// which works for paths, names and prompts
+/// ```text
+/// prefab#npc[
+///     mesh[$mesh, $image]
+///     dialogue[$script, trigger[sphere, scale[1.5]]]
+/// ]
+/// #waiting_room[
+///     camera[]
+///     use#poca[npc, pos[3, -1.25, -1], mesh[poca.obj], image[poca.png], script[test.scr]]
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    pub name: String,
+    pub objects: Vec<Object>,
+}
+impl FromStr for Prefab {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing prefab: {s}") }
+        let (prefix, brackets) = s.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
+        let (prefix, name) = split_prefix(prefix.trim())?;
+        if prefix != "prefab" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let (args, rest) = split_bracket(brackets)?;
+        if !brackets[rest..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
+        let objects = parse_each(&split_args_spanned(&args)?, s.len() - brackets.len(), "object")?;
+        Ok(Self { name, objects })
+    }
+}
+impl Display for Prefab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "prefab#{}[", self.name)?;
+        for o in &self.objects { writeln!(f, "\t{}", o.to_text(1))? }
+        write!(f, "]")
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SceneFields", into = "SceneFields")]
 pub struct Scene {
     pub name:      String,
     pub objects:   Vec<Object>,
     pub cam_pos:   Vec3,
+    /// in degrees, as written
     pub cam_rot:   Vec3,
     pub cam_quat:  Quat,
     pub cam_trans: Mat4,
     pub cam_scale: Vec3,
 }
+/// how a [`Scene`] is written in `.ron` and `.json`
+#[derive(Serialize, Deserialize)]
+struct SceneFields {
+    name: String,
+    #[serde(default)]
+    camera: Camera,
+    #[serde(default)]
+    objects: Vec<Object>,
+}
+#[derive(Serialize, Deserialize)]
+struct Camera {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pos: [f32; 3],
+    #[serde(default, skip_serializing_if = "is_zero")]
+    rot: [f32; 3],
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    scale: [f32; 3],
+}
+impl Default for Camera {
+    fn default() -> Self { Self { pos: [0.0; 3], rot: [0.0; 3], scale: one() } }
+}
+impl From<SceneFields> for Scene {
+    fn from(SceneFields { name, camera, objects }: SceneFields) -> Self {
+        Scene::new(name, objects, camera.pos.into(), camera.rot.into(), camera.scale.into())
+    }
+}
+impl From<Scene> for SceneFields {
+    fn from(scene: Scene) -> Self {
+        let camera = Camera { pos: scene.cam_pos.into(), rot: scene.cam_rot.into(), scale: scene.cam_scale.into() };
+        Self { name: scene.name, camera, objects: scene.objects }
+    }
+}
+fn is_zero(v: &[f32; 3]) -> bool { *v == [0.0; 3] }
+fn is_one(v: &[f32; 3]) -> bool { *v == one() }
+fn one() -> [f32; 3] { [1.0; 3] }
 impl Scene {
+    /// `cam_rot` is in degrees
+    pub fn new(name: String, objects: Vec<Object>, cam_pos: Vec3, cam_rot: Vec3, cam_scale: Vec3) -> Self {
+        let radians = cam_rot.scale(TAU/360.0);
+        let cam_quat = Quat::from_y_rot(radians.y)
+            * Quat::from_x_rot(radians.x)
+            * Quat::from_z_rot(radians.z);
+        let cam_trans = Mat4::from_inverse_transform(cam_pos, cam_scale, cam_quat);
+        let mut scene = Scene { name, cam_pos, cam_rot, cam_scale, cam_quat, cam_trans, objects };
+        scene.assign_pickup_keys();
+        scene
+    }
     pub fn image_paths(&self) -> Vec<String> {
         self.all_objects().iter().filter_map(|(o, _)| {
             if let Image { image_path } | Mesh { image_path, .. } = &o.object_type { Some(image_path.clone()) }
@@ -79,13 +407,31 @@ impl Scene {
             _ => return None
         }, *t))).collect()
     }
-    pub fn dialogue(&self, data: &PlayerData) -> Vec<(&Object, Mat4)> {
+    /// dialogue objects along with the name of the closest named object containing them
+    pub fn dialogue(&self, data: &PlayerData) -> Vec<(&Object, &str, Mat4)> {
         let mut dialogue_objects = Vec::new();
-        for (o, t) in self.all_valid_objects(data) {
-            if let ObjectType::Dialogue { .. } = o.object_type { dialogue_objects.push((o, t)) }
+        for o in &self.objects {
+            for (o, owner, t) in o.all_valid_objects_with_owner(Mat4::IDENTITY, "", data) {
+                if let ObjectType::Dialogue { .. } = o.object_type { dialogue_objects.push((o, owner, t)) }
+            }
         }
         dialogue_objects
     }
+    /// pickups the player hasn't already collected
+    pub fn pickups(&self, data: &PlayerData) -> Vec<(&Object, Mat4)> {
+        let mut pickups = Vec::new();
+        for (o, t) in self.all_valid_objects(data) {
+            if let ObjectType::Pickup { .. } = o.object_type { pickups.push((o, t)) }
+        }
+        pickups
+    }
+    pub fn encounters(&self, data: &PlayerData) -> Vec<(&Object, Mat4)> {
+        let mut encounters = Vec::new();
+        for (o, t) in self.all_valid_objects(data) {
+            if let ObjectType::Encounter { .. } = o.object_type { encounters.push((o, t)) }
+        }
+        encounters
+    }
     pub fn exits(&self, data: &PlayerData) -> Vec<(&Object, Mat4)> {
         let mut exits = Vec::new();
         for (o, t) in self.all_valid_objects(data) {
@@ -93,6 +439,17 @@ impl Scene {
         }
         exits
     }
+    fn assign_pickup_keys(&mut self) {
+        let scope = format!("{}#", self.name);
+        for o in &mut self.objects { o.assign_pickup_keys(&scope) }
+    }
+    /// a pickup key used by more than one pickup, which would be collected together
+    fn duplicate_pickup_key(&self) -> Option<&str> {
+        let keys: Vec<&str> = self.all_objects().into_iter()
+            .filter_map(|(o, _)| if let Pickup { key, .. } = &o.object_type { Some(key.as_str()) } else { None })
+            .collect();
+        keys.iter().enumerate().find(|(i, key)| keys[..*i].contains(key)).map(|(_, key)| *key)
+    }
     pub fn all_objects(&self) -> Vec<(&Object, Mat4)> {
         let mut results = Vec::new();
         for o in &self.objects {
@@ -118,31 +475,41 @@ impl FromStr for Scene {
         if !prefix.is_empty() { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
         let (args, rest) = split_bracket(brackets)?;
         if !brackets[rest..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
-        let args = split_args(&args)?;
-
-        if let [camera, args @ ..] = args.as_slice() {
-            if debug_parse() { println!("parsing camera: {camera}") }
-            let (cam_prefix, cam_bracket) = camera.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
-            if cam_prefix.trim() != "camera" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
-            let (cam_args, cam_rest) = split_bracket(cam_bracket)?;
-            if !cam_bracket[cam_rest..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
-            let mut cam_args = split_args(&cam_args)?;
-            let (cam_pos, cam_rot, cam_scale) = parse_transform(&mut cam_args)?;
-            if !cam_args.is_empty() { return Err(ParseErr::ToManyArgs) }
-
-            let mut objects = Vec::new();
-            for arg in args { objects.push(arg.parse()?) }
+        let offset = s.len() - brackets.len();
+        let args = split_args_spanned(&args)?;
 
-            let cam_quat = Quat::from_y_rot(cam_rot.y)
-                * Quat::from_x_rot(cam_rot.x)
-                * Quat::from_z_rot(cam_rot.z);
-            let cam_trans = Mat4::from_inverse_transform(cam_pos, cam_scale, cam_quat);
-            Ok(Scene { name, cam_pos, cam_rot, cam_scale, cam_quat, cam_trans, objects })
-        } else {
-            Err(ParseErr::NotEnoughArgs)
-        }
+        let Some(((camera, cam_span), args)) = args.split_first() else { return Err(ParseErr::NotEnoughArgs) };
+        let camera = parse_camera(camera).map_err(|e| e.context("camera", cam_span.start + offset..cam_span.end + offset));
+        // objects are parsed even when the camera is wrong so their errors are given too
+        let objects: Result<Vec<Object>, ParseErr> = parse_each(args, offset, "object");
+        let ((cam_pos, cam_rot, cam_scale), objects) = match (camera, objects) {
+            (Ok(camera), Ok(objects)) => (camera, objects),
+            (Err(camera), Err(objects)) => return Err(ParseErr::Many(vec![camera, objects])),
+            (Err(e), _) | (_, Err(e)) => return Err(e),
+        };
+        Ok(Scene::new(name, objects, cam_pos, cam_rot, cam_scale))
+    }
+}
+impl Display for Scene {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "#{}[", self.name)?;
+        writeln!(f, "\tcamera[{}]", transform_args(self.cam_pos, self.cam_rot, self.cam_scale).join(", "))?;
+        for o in &self.objects { writeln!(f, "\t{}", o.to_text(1))? }
+        write!(f, "]")
     }
 }
+/// `camera[pos[..], rot[..], scale[..]]` as its position, rotation in degrees and scale
+pub fn parse_camera(s: &str) -> Result<(Vec3, Vec3, Vec3), ParseErr> {
+    if debug_parse() { println!("parsing camera: {s}") }
+    let (prefix, brackets) = s.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
+    if prefix.trim() != "camera" { return Err(ParseErr::InvalidPrefix(prefix.trim().to_string())) }
+    let (args, rest) = split_bracket(brackets)?;
+    if !brackets[rest..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
+    let mut args = split_args_spanned(&args)?;
+    let transform = parse_transform(&mut args, s.len() - brackets.len())?;
+    if !args.is_empty() { return Err(ParseErr::ToManyArgs) }
+    Ok(transform)
+}
 fn parse_col_type(s: &str) -> Result<ColliderType, ParseErr> {
     if debug_parse() { println!("parsing collider type: {s}") }
     match s {
@@ -152,30 +519,174 @@ fn parse_col_type(s: &str) -> Result<ColliderType, ParseErr> {
         invalid => Err(ParseErr::InvalidColliderType(invalid.to_string()))
     }
 }
-#[derive(Debug)]
+fn col_type_name(col_type: ColliderType) -> &'static str {
+    match col_type {
+        ColliderType::Cylinder => "cylinder",
+        ColliderType::Cube     => "cube",
+        ColliderType::Sphere   => "sphere",
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ObjectType {
     Trigger  { col_type: ColliderType },
     Collider { col_type: ColliderType },
     Image    { image_path: String },
     Mesh     { mesh_path:  String, image_path: String },
     Exit     { exit_scene: String, exit_name: String, collider: Box<Object> },
-    Dialogue { script_path: String, collider: Box<Object> },
+    Dialogue {
+        script_path: String,
+        collider: Box<Object>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auto: Option<AutoStart>,
+        /// higher priority dialogue is picked first when triggers overlap
+        #[serde(default)]
+        priority: f32,
+        /// shown while the player is able to start this dialogue
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prompt: Option<String>,
+    },
     Group    { objects: Vec<Object> },
+    /// grants its reward when interacted with and then is gone for good.
+    /// `key` is what the save remembers it by, made from the scene and object names
+    Pickup   { reward: Reward, objects: Vec<Object>, #[serde(skip)] key: String },
+    /// starts an encounter with an enemy from the [`EnemyCatalogue`](enemies::EnemyCatalogue)
+    /// when the player walks into its trigger
+    Encounter { enemy: String, collider: Box<Object> },
     If       { object: Box<Object>, requirements: Requirements },
-    PointLight { strength: f32, colour: Vec3 },
-    DirLight   { strength: f32, colour: Vec3 },
+    /// the objects of a [`Prefab`] with its parameters filled in from `params`.
+    /// `objects` is empty until the prefab has been found
+    Use      { prefab: String, #[serde(default)] params: Vec<(String, String)>, #[serde(skip)] objects: Vec<Object> },
+    /// `colour` is from 0 to 255
+    PointLight { strength: f32, #[serde(with = "vector")] colour: Vec3 },
+    DirLight   { strength: f32, #[serde(with = "vector")] colour: Vec3 },
+}
+/// vectors are written as `[x, y, z]` in `.ron` and `.json`
+mod vector {
+    use thin_engine::prelude::Vec3;
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+    pub fn serialize<S: Serializer>(v: &Vec3, s: S) -> Result<S::Ok, S::Error> { <[f32; 3]>::from(*v).serialize(s) }
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec3, D::Error> { <[f32; 3]>::deserialize(d).map(Vec3::from) }
+}
+/// how a dialogue object starts its script when the player walks into its trigger
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoStart {
+    Once,
+    /// seconds before the script can be started again
+    Cooldown(f32),
+}
+impl Display for AutoStart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Once => write!(f, "auto"),
+            Self::Cooldown(secs) => write!(f, "auto[cooldown[{secs}]]"),
+        }
+    }
+}
+fn parse_auto(s: &str) -> Result<AutoStart, ParseErr> {
+    if debug_parse() { println!("parsing auto start: {s}") }
+    if s.trim() == "auto" { return Ok(AutoStart::Once) }
+    let (_, inner) = split_call(s)?;
+    match split_args(&inner)?.as_slice() {
+        [once] if once == "once" => Ok(AutoStart::Once),
+        [cooldown] => match split_call(cooldown)? {
+            ("cooldown", secs) => Ok(AutoStart::Cooldown(secs.trim().parse()?)),
+            (prefix, _) => Err(ParseErr::InvalidPrefix(prefix.to_string())),
+        },
+        [] => Err(ParseErr::NotEnoughArgs),
+        _  => Err(ParseErr::ToManyArgs),
+    }
+}
+/// what a pickup gives the player, either `coin`, `coin 3` or `tag[found_note]`.
+/// written the same in `.ron` and `.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Reward {
+    Item(String, u32),
+    Tag(String),
+}
+impl Display for Reward {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Item(item, 1) => write!(f, "{item}"),
+            Self::Item(item, amount) => write!(f, "{item} {amount}"),
+            Self::Tag(tag) => write!(f, "tag[{tag}]"),
+        }
+    }
 }
-#[derive(Debug)]
+impl TryFrom<String> for Reward {
+    type Error = ParseErr;
+    fn try_from(s: String) -> Result<Self, Self::Error> { s.parse() }
+}
+impl From<Reward> for String {
+    fn from(reward: Reward) -> Self { reward.to_string() }
+}
+impl FromStr for Reward {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing reward: {s}") }
+        if let Ok(("tag", tag)) = split_call(s) { return Ok(Self::Tag(tag.trim().to_string())) }
+        match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [item] => Ok(Self::Item(items::item_catalogue().check(item)?, 1)),
+            [item, amount] => Ok(Self::Item(
+                items::item_catalogue().check(item)?,
+                amount.parse().map_err(|_| ParseErr::InvalidIndex(amount.to_string()))?,
+            )),
+            [] => Err(ParseErr::NotEnoughArgs),
+            _  => Err(ParseErr::ToManyArgs),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ObjectFields", into = "ObjectFields")]
 pub struct Object {
     pub name: String,
     pub object_type: ObjectType,
     pos:   Vec3,
+    /// in degrees, as written
     rot:   Vec3,
     quat:  Quat,
     pub trans: Mat4,
     scale: Vec3,
 }
+/// how an [`Object`] is written in `.ron` and `.json`
+#[derive(Serialize, Deserialize)]
+struct ObjectFields {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
+    object_type: ObjectType,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pos: [f32; 3],
+    #[serde(default, skip_serializing_if = "is_zero")]
+    rot: [f32; 3],
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    scale: [f32; 3],
+}
+impl TryFrom<ObjectFields> for Object {
+    type Error = ParseErr;
+    fn try_from(ObjectFields { name, object_type, pos, rot, scale }: ObjectFields) -> Result<Self, Self::Error> {
+        if let Encounter { enemy, .. } = &object_type { enemies::enemy_catalogue().check(enemy)?; }
+        Ok(Object::new(name, object_type, pos.into(), rot.into(), scale.into()))
+    }
+}
+impl From<Object> for ObjectFields {
+    fn from(o: Object) -> Self {
+        Self { name: o.name, object_type: o.object_type, pos: o.pos.into(), rot: o.rot.into(), scale: o.scale.into() }
+    }
+}
 impl Object {
+    /// `rot` is in degrees
+    pub fn new(name: String, object_type: ObjectType, pos: Vec3, rot: Vec3, scale: Vec3) -> Self {
+        let radians = rot.scale(TAU/360.0);
+        let quat = Quat::from_x_rot(radians.x)
+                * Quat::from_y_rot(radians.y)
+                * Quat::from_z_rot(radians.z);
+        let trans = Mat4::from_transform(pos, scale, quat);
+        Self { name, object_type, pos, rot, quat, trans, scale }
+    }
+    /// `pos`, `rot` in degrees and `scale` as they were written
+    pub fn local_transform(&self) -> (Vec3, Vec3, Vec3) { (self.pos, self.rot, self.scale) }
     pub fn triggers(&self, data: &PlayerData) -> Vec<(ColliderType, Mat4)> {
         let mut results = Vec::new(); 
         use ObjectType::*;
@@ -184,13 +695,37 @@ impl Object {
         }
         results
     }
+    /// a pickup is kept track of by its name, or by where it is if it has none.
+    /// `scope` is the scene and the `use` it's in, so each copy of a prefab has keys of its own
+    fn assign_pickup_keys(&mut self, scope: &str) {
+        let place = format!("@{}:{}:{}", self.pos.x, self.pos.y, self.pos.z);
+        match &mut self.object_type {
+            Pickup { objects, key, .. } => {
+                *key = if self.name.is_empty() { format!("{scope}pickup{place}") } else { format!("{scope}{}", self.name) };
+                for o in objects { o.assign_pickup_keys(scope) }
+            },
+            Use { objects, prefab, .. } => {
+                let scope = if self.name.is_empty() { format!("{scope}{prefab}{place}/") } else { format!("{scope}{}/", self.name) };
+                for o in objects { o.assign_pickup_keys(&scope) }
+            },
+            Group { objects } => for o in objects { o.assign_pickup_keys(scope) },
+            If { object: o, .. } | Exit { collider: o, .. }
+            | Dialogue { collider: o, .. } | Encounter { collider: o, .. } =>
+                o.assign_pickup_keys(scope),
+            _ => (),
+        }
+    }
+    fn is_picked_up(&self, data: &PlayerData) -> bool {
+        matches!(&self.object_type, Pickup { key, .. } if data.picked_up.contains(key))
+    }
     fn all_objects_with_parent(&self, mut t: Mat4) -> Vec<(&Object, Mat4)> {
         let mut results = Vec::new();
         t *= self.trans;
         use ObjectType::*;
         match &self.object_type {
-            Group { objects } => for o in objects { results.append(&mut o.all_objects_with_parent(t)) },
-            If { object: o, .. } | Exit { collider: o, .. } | Dialogue { collider: o, .. } =>
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } => for o in objects { results.append(&mut o.all_objects_with_parent(t)) },
+            If { object: o, .. } | Exit { collider: o, .. }
+            | Dialogue { collider: o, .. } | Encounter { collider: o, .. } =>
                 results.append(&mut o.all_objects_with_parent(t)),
             _ => (),
         }
@@ -202,8 +737,9 @@ impl Object {
         let t = self.trans;
         use ObjectType::*;
         match &self.object_type {
-            Group { objects } => for o in objects { results.append(&mut o.all_objects_with_parent(t)) },
-            If { object: o, .. } | Exit { collider: o, .. } | Dialogue { collider: o, .. } =>
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } => for o in objects { results.append(&mut o.all_objects_with_parent(t)) },
+            If { object: o, .. } | Exit { collider: o, .. }
+            | Dialogue { collider: o, .. } | Encounter { collider: o, .. } =>
                 results.append(&mut o.all_objects_with_parent(t)),
             _ => (),
         }
@@ -212,11 +748,12 @@ impl Object {
     }
     fn all_valid_objects_with_parent(&self, mut t: Mat4, data: &PlayerData) -> Vec<(&Object, Mat4)> {
         let mut results = Vec::new();
+        if self.is_picked_up(data) { return results }
         t *= self.trans;
         use ObjectType::*;
         match &self.object_type {
-            Group { objects } => for o in objects { results.append(&mut o.all_valid_objects_with_parent(t, data)) },
-            Exit { collider: o, .. } | Dialogue { collider: o, .. } =>
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } => for o in objects { results.append(&mut o.all_valid_objects_with_parent(t, data)) },
+            Exit { collider: o, .. } | Dialogue { collider: o, .. } | Encounter { collider: o, .. } =>
                 results.append(&mut o.all_valid_objects_with_parent(t, data)),
             If { requirements, object } => if requirements.evaluate(data) {
                 results.append(&mut object.all_valid_objects_with_parent(t, data))
@@ -226,13 +763,33 @@ impl Object {
         results.push((self, t));
         results
     }
+    fn all_valid_objects_with_owner<'a>(
+        &'a self, mut t: Mat4, mut owner: &'a str, data: &PlayerData
+    ) -> Vec<(&'a Object, &'a str, Mat4)> {
+        let mut results = Vec::new();
+        if self.is_picked_up(data) { return results }
+        t *= self.trans;
+        if !self.name.is_empty() { owner = &self.name }
+        use ObjectType::*;
+        match &self.object_type {
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } => for o in objects { results.append(&mut o.all_valid_objects_with_owner(t, owner, data)) },
+            Exit { collider: o, .. } | Dialogue { collider: o, .. } | Encounter { collider: o, .. } =>
+                results.append(&mut o.all_valid_objects_with_owner(t, owner, data)),
+            If { requirements, object } if requirements.evaluate(data) =>
+                results.append(&mut object.all_valid_objects_with_owner(t, owner, data)),
+            _ => (),
+        }
+        results.push((self, owner, t));
+        results
+    }
     fn all_valid_objects(&self, data: &PlayerData) -> Vec<(&Object, Mat4)> {
         let mut results = Vec::new();
+        if self.is_picked_up(data) { return results }
         let t = self.trans;
         use ObjectType::*;
         match &self.object_type {
-            Group { objects } => for o in objects { results.append(&mut o.all_valid_objects_with_parent(t, data)) },
-            Exit { collider, .. } | Dialogue { collider, .. } => results
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } => for o in objects { results.append(&mut o.all_valid_objects_with_parent(t, data)) },
+            Exit { collider, .. } | Dialogue { collider, .. } | Encounter { collider, .. } => results
                 .append(&mut collider.all_valid_objects_with_parent(t, data)),
             If { requirements, object } => if requirements.evaluate(data) {
                 results.append(&mut object.all_valid_objects_with_parent(t, data))
@@ -243,6 +800,120 @@ impl Object {
         results
     }
 }
+impl Object {
+    /// the object as it would be written in a scene, with the objects inside
+    /// groups and pickups each on their own line indented one more than `depth`
+    fn to_text(&self, depth: usize) -> String {
+        let mut args = Vec::new();
+        let mut children: &[Object] = &[];
+        let prefix = match &self.object_type {
+            Trigger  { col_type } => { args.push(col_type_name(*col_type).to_string()); "trigger" },
+            Collider { col_type } => { args.push(col_type_name(*col_type).to_string()); "collider" },
+            Image { image_path } => { args.push(lexer::quote(image_path)); "image" },
+            Mesh { mesh_path, image_path } => { args.extend([lexer::quote(mesh_path), lexer::quote(image_path)]); "mesh" },
+            Exit { exit_scene, exit_name, collider } => {
+                args.extend([lexer::quote(exit_scene), lexer::quote(exit_name), collider.to_text(depth)]);
+                "exit"
+            },
+            Dialogue { script_path, collider, auto, priority, prompt } => {
+                args.extend([lexer::quote(script_path), collider.to_text(depth)]);
+                if let Some(auto) = auto { args.push(auto.to_string()) }
+                if *priority != 0.0 { args.push(format!("priority[{priority}]")) }
+                if let Some(prompt) = prompt { args.push(format!("prompt[{}]", lexer::quote(prompt))) }
+                "dialogue"
+            },
+            Group { objects } => { children = objects; "" },
+            Pickup { reward, objects, .. } => { args.push(reward.to_string()); children = objects; "pickup" },
+            Encounter { enemy, collider } => { args.extend([enemy.clone(), collider.to_text(depth)]); "encounter" },
+            If { object, requirements } => { args.extend([requirements.to_string(), object.to_text(depth)]); "if" },
+            Use { prefab, params, .. } => {
+                args.push(prefab.clone());
+                args.extend(params.iter().map(|(param, value)| format!("{param}[{}]", lexer::quote(value))));
+                "use"
+            },
+            PointLight { strength, colour } | DirLight { strength, colour } => {
+                args.extend([strength.to_string(), format!("colour[{}, {}, {}]", colour.x, colour.y, colour.z)]);
+                if let PointLight { .. } = self.object_type { "point_light" } else { "dir_light" }
+            },
+        };
+        args.extend(transform_args(self.pos, self.rot, self.scale));
+        let name = if self.name.is_empty() { String::new() } else { format!("#{}", self.name) };
+        let mut text = format!("{prefix}{name}[{}", args.join(", "));
+        for child in children { text += &format!("\n{}{}", "\t".repeat(depth + 1), child.to_text(depth + 1)) }
+        if !children.is_empty() { text += &format!("\n{}", "\t".repeat(depth)) }
+        text + "]"
+    }
+}
+impl Object {
+    /// fills in the objects of every `use` in this object that hasn't been filled in yet.
+    /// prefabs that can't be found are left for later unless `strict`,
+    /// `using` is the prefabs already being filled in further out and `span` is where this object is
+    fn expand(&mut self, prefabs: &[Prefab], strict: bool, using: &mut Vec<String>, span: Range<usize>) -> Result<(), ParseErr> {
+        let name = if self.name.is_empty() { "use".to_string() } else { format!("use#{}", self.name) };
+        match &mut self.object_type {
+            Use { prefab, params, objects } if objects.is_empty() => {
+                let context = |e: ParseErr| e.context(format!("object `{name}`"), span.clone());
+                let Some(found) = prefabs.iter().find(|p| p.name == *prefab) else {
+                    return if strict { Err(context(ParseErr::InvalidPrefab(prefab.clone()))) } else { Ok(()) }
+                };
+                if using.contains(prefab) { return Err(context(ParseErr::RecursivePrefab(prefab.clone()))) }
+                using.push(prefab.clone());
+                let mut used = Vec::new();
+                *objects = found.objects.clone();
+                // the prefab's objects aren't written here, so errors in them point at the whole `use`
+                for o in objects.iter_mut() {
+                    o.substitute(params, &mut used).map_err(context)?;
+                    o.expand(prefabs, strict, using, 0..span.len()).map_err(context)?;
+                }
+                using.pop();
+                if let Some((unused, _)) = params.iter().find(|(param, _)| !used.contains(param)) {
+                    return Err(context(ParseErr::InvalidParam(unused.clone())))
+                }
+            },
+            Group { objects } | Pickup { objects, .. } | Use { objects, .. } =>
+                for o in objects { o.expand(prefabs, strict, using, span.clone())? },
+            If { object: o, .. } | Exit { collider: o, .. }
+            | Dialogue { collider: o, .. } | Encounter { collider: o, .. } => o.expand(prefabs, strict, using, span)?,
+            _ => (),
+        }
+        Ok(())
+    }
+    /// replaces the arguments written as `$param` with their values from `params`, noting which were used
+    fn substitute(&mut self, params: &[(String, String)], used: &mut Vec<String>) -> Result<(), ParseErr> {
+        fn fill(s: &mut String, params: &[(String, String)], used: &mut Vec<String>) -> Result<(), ParseErr> {
+            let Some(param) = s.strip_prefix('$') else { return Ok(()) };
+            let (param, value) = params.iter().find(|(name, _)| name == param)
+                .ok_or_else(|| ParseErr::MissingParam(param.to_string()))?;
+            used.push(param.clone());
+            *s = value.clone();
+            Ok(())
+        }
+        match &mut self.object_type {
+            Image { image_path } => fill(image_path, params, used)?,
+            Mesh { mesh_path, image_path } => for s in [mesh_path, image_path] { fill(s, params, used)? },
+            Exit { exit_scene, exit_name, collider } => {
+                for s in [exit_scene, exit_name] { fill(s, params, used)? }
+                collider.substitute(params, used)?;
+            },
+            Dialogue { script_path, collider, prompt, .. } => {
+                fill(script_path, params, used)?;
+                if let Some(prompt) = prompt { fill(prompt, params, used)? }
+                collider.substitute(params, used)?;
+            },
+            Use { params: values, objects, .. } => {
+                for (_, value) in values { fill(value, params, used)? }
+                for o in objects { o.substitute(params, used)? }
+            },
+            Group { objects } | Pickup { objects, .. } => for o in objects { o.substitute(params, used)? },
+            If { object: o, .. } | Encounter { collider: o, .. } => o.substitute(params, used)?,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "{}", self.to_text(0)) }
+}
 impl FromStr for Object {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -252,48 +923,70 @@ impl FromStr for Object {
 
         let (args, rest) = split_bracket(brackets)?;
         if !brackets[rest..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
-        let mut args = split_args(&args)?;
+        let offset = s.len() - brackets.len();
+        let mut spanned = split_args_spanned(&args)?;
 
-        let (pos, rot, scale) = parse_transform(&mut args)?;
-        let quat = Quat::from_x_rot(rot.x)
-                * Quat::from_y_rot(rot.y)
-                * Quat::from_z_rot(rot.z);
+        let (pos, rot, scale) = parse_transform(&mut spanned, offset)?;
+        let args: Vec<&str> = spanned.iter().map(|(arg, _)| arg.as_str()).collect();
+        // puts an error from the argument at `i` in context
+        let at = |i: usize, kind: &str| {
+            let (arg, span) = &spanned[i];
+            let (context, span) = (format!("{kind} `{}`", describe(arg)), span.start + offset..span.end + offset);
+            move |e: ParseErr| e.context(context, span)
+        };
+        let radians = rot.scale(TAU/360.0);
+        let quat = Quat::from_x_rot(radians.x)
+                * Quat::from_y_rot(radians.y)
+                * Quat::from_z_rot(radians.z);
         let trans = Mat4::from_transform(pos, scale, quat);
         Ok(Object {
             object_type: match (prefix, args.as_slice()) {
                 ("trigger",  [_, _, ..]) => return Err(ParseErr::ToManyArgs),
-                ("trigger",  [col_type]) => ObjectType::Trigger { col_type: parse_col_type(col_type)? },
+                ("trigger",  [col_type]) => ObjectType::Trigger { col_type: parse_col_type(col_type).map_err(at(0, "argument"))? },
                 ("trigger",  []        ) => return Err(ParseErr::NotEnoughArgs),
 
                 ("collider", [_, _, ..]) => return Err(ParseErr::ToManyArgs),
-                ("collider", [col_type]) => ObjectType::Collider { col_type: parse_col_type(col_type)? },
+                ("collider", [col_type]) => ObjectType::Collider { col_type: parse_col_type(col_type).map_err(at(0, "argument"))? },
                 ("collider", []        ) => return Err(ParseErr::NotEnoughArgs),
 
                 ("exit", [exit_scene, exit_name, collider]) => ObjectType::Exit {
                     exit_scene: exit_scene.to_string(),
                     exit_name:  exit_name .to_string(),
-                    collider: Box::new(collider.parse()?)
+                    collider: Box::new(collider.parse().map_err(at(2, "object"))?)
                 },
                 ("exit", [_, _, _, _, ..]) => return Err(ParseErr::ToManyArgs),
                 ("exit", _               ) => return Err(ParseErr::NotEnoughArgs),
                 
-                ("dialogue", [script_path, collider]) => ObjectType::Dialogue {
-                    script_path: script_path.to_string(),
-                    collider: Box::new(collider.parse()?),
+                ("dialogue", [script_path, collider, options @ ..]) => {
+                    let mut auto = None;
+                    let mut priority = 0.0;
+                    let mut prompt = None;
+                    for (i, option) in options.iter().enumerate() {
+                        if option.starts_with("auto") { auto = Some(parse_auto(option).map_err(at(i + 2, "argument"))?); continue }
+                        match split_call(option).map_err(at(i + 2, "argument"))? {
+                            ("priority", p) => priority = p.trim().parse().map_err(ParseErr::from).map_err(at(i + 2, "argument"))?,
+                            ("prompt",   p) => prompt = Some(p.trim().to_string()),
+                            (prefix, _) => return Err(at(i + 2, "argument")(ParseErr::InvalidPrefix(prefix.to_string()))),
+                        }
+                    }
+                    ObjectType::Dialogue {
+                        script_path: script_path.to_string(),
+                        collider: Box::new(collider.parse().map_err(at(1, "object"))?),
+                        auto, priority, prompt,
+                    }
                 },
-                ("dialogue", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
-                ("dialogue", _            ) => return Err(ParseErr::NotEnoughArgs),
+                ("dialogue", _) => return Err(ParseErr::NotEnoughArgs),
                 
                 ("point_light", [strength, colour]) => ObjectType::PointLight {
-                    strength: strength.parse()?,
-                    colour: parse_colour(colour)?
+                    strength: strength.parse().map_err(ParseErr::from).map_err(at(0, "argument"))?,
+                    colour: parse_colour(colour).map_err(at(1, "argument"))?
                 },
                 ("point_light", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
                 ("point_light",  _           ) => return Err(ParseErr::NotEnoughArgs),
                 
                 ("dir_light", [strength, colour]) => ObjectType::DirLight {
-                    strength: strength.parse()?,
-                    colour: parse_colour(colour)?
+                    strength: strength.parse().map_err(ParseErr::from).map_err(at(0, "argument"))?,
+                    colour: parse_colour(colour).map_err(at(1, "argument"))?
                 },
                 ("dir_light", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
                 ("dir_light",  _           ) => return Err(ParseErr::NotEnoughArgs),
@@ -309,63 +1002,289 @@ impl FromStr for Object {
                 ("mesh", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
                 ("mesh", _            ) => return Err(ParseErr::NotEnoughArgs),
                 
+                ("pickup", [reward, ..]) => ObjectType::Pickup {
+                    reward: reward.parse().map_err(at(0, "argument"))?,
+                    objects: parse_each(&spanned[1..], offset, "object")?,
+                    key: String::new(),
+                },
+                ("pickup", []) => return Err(ParseErr::NotEnoughArgs),
+
+                ("encounter", [enemy, collider]) => ObjectType::Encounter {
+                    enemy: enemies::enemy_catalogue().check(enemy).map_err(at(0, "argument"))?,
+                    collider: Box::new(collider.parse().map_err(at(1, "object"))?),
+                },
+                ("encounter", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
+                ("encounter", _            ) => return Err(ParseErr::NotEnoughArgs),
+
                 ("if", [requirements, object]) => ObjectType::If {
-                    requirements: requirements.parse()?,
-                    object: Box::new(object.parse()?)
+                    requirements: requirements.parse().map_err(at(0, "argument"))?,
+                    object: Box::new(object.parse().map_err(at(1, "object"))?)
                 },
                 ("if", [_, _, _, ..]) => return Err(ParseErr::ToManyArgs),
                 ("if", _            ) => return Err(ParseErr::NotEnoughArgs),
                 
-                ("", args @ [..]) => {
-                    let mut objects = Vec::new();
-                    for arg in args { objects.push(arg.parse()?) }
-                    ObjectType::Group { objects }
+                ("use", [prefab, params @ ..]) => ObjectType::Use {
+                    prefab: prefab.to_string(),
+                    params: params.iter().enumerate().map(|(i, param)| match split_call(param) {
+                        Ok((param, value)) => Ok((param.to_string(), value.trim().to_string())),
+                        Err(e) => Err(at(i + 1, "argument")(e)),
+                    }).collect::<Result<_, _>>()?,
+                    objects: Vec::new(),
                 },
+                ("use", []) => return Err(ParseErr::NotEnoughArgs),
+
+                ("", _) => ObjectType::Group { objects: parse_each(&spanned, offset, "object")? },
                 (prefix, ..) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
             }, pos, rot, scale, quat, trans, name
         })
     }
 }
-fn parse_transform(args: &mut Vec<String>) -> Result<(Vec3, Vec3, Vec3), ParseErr> {
+/// takes the `pos`, `rot` and `scale` arguments out of `args`,
+/// `offset` is where the arguments start in the text errors should point into
+fn parse_transform(args: &mut Vec<(String, Range<usize>)>, offset: usize) -> Result<(Vec3, Vec3, Vec3), ParseErr> {
     if debug_parse() { println!("parsing transform: {args:?}") }
     let mut pos = None;
     let mut rot = None;
     let mut scale = None;
 
     let mut remove_indices = Vec::new();
-    for (i, arg) in args.iter().enumerate() {
-        let Some((prefix, brackets)) = arg.split_once('[') else { continue };
-        let (args, remainder) = split_bracket(brackets)?;
-        if !brackets[remainder..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
-        let args = split_args(&args)?;
-        match prefix.trim_start() {
-            "pos" | "rot" | "scale" => match args.as_slice() {
-                [_, _, _, _, ..]  => return Err(ParseErr::ToManyArgs),
-                [_, _] | [] => return Err(ParseErr::NotEnoughArgs),
-                [splat] => {
-                    remove_indices.insert(0, i);
-                    if prefix != "scale" { return Err(ParseErr::NotEnoughArgs) }
-                    scale = Some(Vec3::splat(splat.parse()?));
-                },
-                [x, y, z] => {
-                    remove_indices.insert(0, i);
-                    let v = vec3(x.parse()?, y.parse()?, z.parse()?);
-                    match prefix {
-                        "pos"   => pos   = Some(v),
-                        "rot"   => rot   = Some(v.scale(TAU/360.0)),
-                        "scale" => scale = Some(v),
-                        _ => unreachable!()
-                    }
-                },
-            },
-            _ => continue
+    for (i, (arg, span)) in args.iter().enumerate() {
+        let Some((prefix, _)) = arg.split_once('[') else { continue };
+        let prefix = prefix.trim_start();
+        if !matches!(prefix, "pos" | "rot" | "scale") { continue }
+        let v = parse_vector(arg, prefix).map_err(|e| e.context(format!("argument `{prefix}`"), span.start + offset..span.end + offset))?;
+        remove_indices.insert(0, i);
+        match prefix {
+            "pos"   => pos   = Some(v),
+            "rot"   => rot   = Some(v),
+            "scale" => scale = Some(v),
+            _ => unreachable!()
         }
     }
     for i in remove_indices { args.remove(i); }
     Ok((pos.unwrap_or(Vec3::ZERO), rot.unwrap_or(Vec3::ZERO), scale.unwrap_or(Vec3::ONE)))
 }
+/// the `pos`, `rot` and `scale` arguments for a transform, leaving out any that are the default
+fn transform_args(pos: Vec3, rot: Vec3, scale: Vec3) -> Vec<String> {
+    let mut args = Vec::new();
+    if pos != Vec3::ZERO { args.push(format!("pos[{}, {}, {}]", pos.x, pos.y, pos.z)) }
+    if rot != Vec3::ZERO { args.push(format!("rot[{}, {}, {}]", rot.x, rot.y, rot.z)) }
+    if scale == Vec3::splat(scale.x) && scale != Vec3::ONE { args.push(format!("scale[{}]", scale.x)) }
+    else if scale != Vec3::ONE { args.push(format!("scale[{}, {}, {}]", scale.x, scale.y, scale.z)) }
+    args
+}
+/// `pos[x, y, z]`, `rot[x, y, z]`, `scale[x, y, z]` or `scale[splat]`
+fn parse_vector(s: &str, prefix: &str) -> Result<Vec3, ParseErr> {
+    let (_, brackets) = s.split_once('[').ok_or(ParseErr::NoOpenBracket)?;
+    let (args, remainder) = split_bracket(brackets)?;
+    if !brackets[remainder..].trim().is_empty() { return Err(ParseErr::EarlyCloseBracket) }
+    match split_args(&args)?.as_slice() {
+        [_, _, _, _, ..]  => Err(ParseErr::ToManyArgs),
+        [_, _] | [] => Err(ParseErr::NotEnoughArgs),
+        [splat] if prefix == "scale" => Ok(Vec3::splat(splat.parse()?)),
+        [_] => Err(ParseErr::NotEnoughArgs),
+        [x, y, z] => Ok(vec3(x.parse()?, y.parse()?, z.parse()?)),
+    }
+}
 fn split_prefix(s: &str) -> Result<(&str, String), ParseErr> {
     if debug_parse() { println!("splitting prefix {s}") }
     let (prefix, name) = s.split_once('#').unwrap_or((s, ""));
     Ok((prefix, name.to_string()))
 }
+
+#[test]
+fn errors_point_at_their_source() {
+    let source = "#room[
+    camera[pos[0, 0, 0]]
+    exit#door[hall, door, trigger[cub]]
+    point_light[bright, colour[255, 255, 255]]
+]";
+    let err = source.parse::<GameScenes>().unwrap_err();
+    // both objects are reported rather than stopping at the first
+    assert_eq!(err.count(), 2);
+    let report = err.report("room.scn", source);
+    assert!(report.contains(" --> room.scn:3:35\n"));
+    assert!(report.contains("= in scene `#room` > object `exit#door` > object `trigger` > argument `cub`"));
+    assert!(report.contains(" --> room.scn:4:17\n"));
+
+    // comments inside an object don't move where the errors after them are
+    let commented = source.replace("exit#door[hall, ", "exit#door[hall, # the way out\n\t");
+    let report = commented.parse::<GameScenes>().unwrap_err().report("room.scn", &commented);
+    assert!(report.contains(" --> room.scn:4:16\n"), "{report}");
+}
+#[test]
+fn scenes_load_from_many_files() {
+    let dir = std::env::temp_dir().join(format!("sword-ward-scenes-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("rooms")).unwrap();
+    std::fs::write(dir.join("a.scn"), "include[rooms/c.scn]\n#a[camera[], use[lamp]]").unwrap();
+    std::fs::write(dir.join("b.scn"), "prefab#lamp[point_light[1, colour[255, 255, 255]]]\n#b[camera[]]").unwrap();
+    std::fs::write(dir.join("rooms/c.scn"), "include[../b.scn]\n#c[camera[]]").unwrap();
+    let scenes = GameScenes::load(&dir).unwrap();
+    let names: Vec<&str> = scenes.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["a", "c", "b"]);
+    // the prefab comes from another file
+    assert_eq!(scenes["a"].all_objects().len(), 2);
+
+    std::fs::write(dir.join("d.scn"), "#d[camera[]]\n#c[camera[]]").unwrap();
+    let err = GameScenes::load(&dir).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let [location] = err.locations().try_into().unwrap();
+    assert!(matches!(location.err, ParseErr::DuplicateScene(s) if s == "c"));
+    assert_eq!((location.file.unwrap().ends_with("d.scn"), location.span), (true, Some(13..25)));
+    assert!(matches!("#x[camera[]]\n#x[camera[]]".parse::<GameScenes>(), Err(ParseErr::Context { .. })));
+}
+#[test]
+fn prefabs_expand_into_objects() {
+    let source = "prefab#npc[
+    mesh[$mesh, poca.png, pos[0, 1, 0]]
+    dialogue[$script, trigger[sphere]]
+]
+#room[
+    camera[]
+    use#poca[npc, pos[3, 0, 0], mesh[poca.obj], script[test.scr]]
+    use#clerk[npc, pos[-3, 0, 0], mesh[clerk.obj], script[clerk.scr]]
+]";
+    let scenes: GameScenes = source.parse().unwrap();
+    let room = &scenes["room"];
+    let meshes: Vec<(String, Vec3)> = room.all_objects().into_iter().filter_map(|(o, t)| match &o.object_type {
+        Mesh { mesh_path, .. } => Some((mesh_path.clone(), t.position())),
+        _ => None,
+    }).collect();
+    assert_eq!(meshes, [("poca.obj".to_string(), vec3(3.0, 1.0, 0.0)), ("clerk.obj".to_string(), vec3(-3.0, 1.0, 0.0))]);
+    assert_eq!(room.script_paths(), ["test.scr", "clerk.scr"]);
+    let owners: Vec<&str> = room.dialogue(&PlayerData::default()).into_iter().map(|(_, owner, _)| owner).collect();
+    assert_eq!(owners, ["poca", "clerk"]);
+    assert_eq!(scenes.to_string().parse::<GameScenes>().unwrap(), scenes);
+
+    let missing = source.replace(", script[clerk.scr]", "");
+    let err = missing.parse::<GameScenes>().unwrap_err();
+    assert!(matches!(err.locations()[0].err, ParseErr::MissingParam(p) if p == "script"));
+    let unused = source.replace("script[clerk.scr]", "script[clerk.scr], image[clerk.png]");
+    let err = unused.parse::<GameScenes>().unwrap_err();
+    let location = &err.locations()[0];
+    assert!(matches!(location.err, ParseErr::InvalidParam(p) if p == "image"));
+    assert!(unused[location.span.clone().unwrap()].starts_with("use#clerk["));
+
+    // each copy of a prefab has its own pickups
+    let stalls = "prefab#stall[pickup#coin[tag[coin], trigger[sphere]]]
+#market[camera[], use#left[stall], use[stall, pos[4, 0, 0]], pickup[tag[found], trigger[sphere]]]";
+    let scenes: GameScenes = stalls.parse().unwrap();
+    let keys: Vec<&str> = scenes["market"].all_objects().into_iter().filter_map(|(o, _)| match &o.object_type {
+        Pickup { key, .. } => Some(key.as_str()),
+        _ => None,
+    }).collect();
+    assert_eq!(keys, ["market#left/coin", "market#stall@4:0:0/coin", "market#pickup@0:0:0"]);
+    let err = stalls.replace("use#left[stall]", "use#left[stall], use#left[stall]").parse::<GameScenes>().unwrap_err();
+    assert!(matches!(&err.locations()[0].err, ParseErr::DuplicatePickup(k) if k == "market#left/coin"));
+}
+#[test]
+fn scenes_convert_to_ron_and_json() {
+    let scenes: GameScenes = "include[hall.scn]
+prefab#sign[image[$image, scale[2]]]
+#room[
+    camera[pos[0, 0, 10], rot[0, 180, 0]]
+    use#notice[sign, pos[1, 2, 3], image[notice.png]]
+    pickup[tag[found_note], if[!read, image[note.png]]]
+    dialogue[test.scr, trigger[sphere], auto[cooldown[2]], prompt[\"Read, maybe\"]]
+    point_light[2, colour[255, 200, 0]]
+]".parse().unwrap();
+    for format in [Format::Ron, Format::Json] {
+        let text = format.write(&scenes).unwrap();
+        let mut parsed: GameScenes = format.parse(&text).unwrap();
+        // where includes were written is only known in the bracket format
+        parsed.includes = scenes.includes.clone();
+        assert_eq!(parsed, scenes);
+    }
+    let source = "{\"scenes\": [{\"name\": \"a\",
+        \"objects\": [{\"object_type\": {\"trigger\": {\"col_type\": \"cub\"}}}]}]}";
+    let err = Format::Json.parse::<GameScenes>(source).unwrap_err();
+    let [location] = err.locations().try_into().unwrap();
+    // json errors point at the end of what's wrong
+    assert!(source[..location.span.unwrap().end].ends_with("\"cub\""));
+}
+#[test]
+fn exits_are_checked() {
+    let scenes: GameScenes = "
+        #hall[camera[], exit#door[yard, gate, trigger[cube]], exit#window[attic, window, trigger[cube]]]
+        #yard[camera[], [exit#gate[hall, porch, trigger[cube]]]]
+    ".parse().unwrap();
+    assert_eq!(scenes.check_exits(), [
+        "exit `window` in scene `hall` leads to scene `attic` which doesn't exist",
+        "exit `gate` in scene `yard` arrives at exit `porch` which scene `hall` doesn't have",
+    ].map(str::to_string));
+}
+#[cfg(test)]
+use proptest::{prelude::*, collection::vec, option};
+#[cfg(test)]
+fn arb_number() -> impl Strategy<Value = String> {
+    prop_oneof![(-100i32..100).prop_map(|n| n.to_string()), (-100.0f32..100.0).prop_map(|n| n.to_string())]
+}
+#[cfg(test)]
+fn arb_transform() -> impl Strategy<Value = Vec<String>> {
+    let vector = |n: BoxedStrategy<String>| (n.clone(), n.clone(), n).prop_map(|(x, y, z)| format!("{x}, {y}, {z}"));
+    // a scale of 0 can't be inverted for the camera
+    let scale = (0.1f32..10.0).prop_map(|n| n.to_string()).boxed();
+    (option::of(vector(arb_number().boxed())), option::of(vector(arb_number().boxed())),
+        option::of(prop_oneof![vector(scale.clone()), scale]))
+        .prop_map(|(pos, rot, scale)| [("pos", pos), ("rot", rot), ("scale", scale)].into_iter()
+            .filter_map(|(prefix, v)| Some(format!("{prefix}[{}]", v?))).collect())
+}
+/// an object as it could be written in a scene
+#[cfg(test)]
+fn arb_object() -> BoxedStrategy<String> {
+    let path = prop_oneof!["[a-z]{1,6}\\.(png|obj)", Just("\"a, [b].png\"".to_string())];
+    let name = option::of("[a-z]{1,5}").prop_map(|n| n.map(|n| format!("#{n}")).unwrap_or_default());
+    let call = |prefix: &str, name: String, mut args: Vec<String>, transform: Vec<String>| {
+        args.extend(transform);
+        format!("{prefix}{name}[{}]", args.join(", "))
+    };
+    let col_type = prop::sample::select(vec!["cube", "sphere", "cylinder"]);
+    let leaf = prop_oneof![
+        (prop::sample::select(vec!["trigger", "collider"]), col_type, arb_transform())
+            .prop_map(move |(prefix, c, t)| call(prefix, String::new(), vec![c.to_string()], t)),
+        (path.clone(), arb_transform()).prop_map(move |(p, t)| call("image", String::new(), vec![p], t)),
+        (path.clone(), path.clone(), arb_transform()).prop_map(move |(m, p, t)| call("mesh", String::new(), vec![m, p], t)),
+        (prop::sample::select(vec!["point_light", "dir_light"]), arb_number(), vec(0u8..=255, 3), arb_transform())
+            .prop_map(move |(prefix, s, c, t)| call(prefix, String::new(), vec![s, format!("colour[{}, {}, {}]", c[0], c[1], c[2])], t)),
+    ];
+    leaf.prop_recursive(2, 12, 3, move |inner| {
+        let requirements = prop::sample::select(vec!["met", "!met & fitness >= 2", "[a | b] & affinity[poca] < -1"]);
+        let auto = prop::sample::select(vec!["auto", "auto[once]", "auto[cooldown[2.5]]"]);
+        prop_oneof![
+            (name.clone(), vec(inner.clone(), 0..3), arb_transform()).prop_map(move |(n, o, t)| call("", n, o, t)),
+            (name.clone(), "[a-z]{1,5}", vec(inner.clone(), 0..3), arb_transform())
+                .prop_map(move |(n, tag, mut o, t)| { o.insert(0, format!("tag[{tag}]")); call("pickup", n, o, t) }),
+            (name.clone(), "[a-z]{1,5}", "[a-z]{1,5}", inner.clone(), arb_transform())
+                .prop_map(move |(n, scene, exit, o, t)| call("exit", n, vec![scene, exit, o], t)),
+            (path.clone(), inner.clone(), option::of(auto), option::of(arb_number()), option::of("[A-Za-z ,?]{1,12}"))
+                .prop_map(move |(p, o, auto, priority, prompt)| {
+                    let mut args = vec![p, o];
+                    args.extend(auto.map(str::to_string));
+                    args.extend(priority.map(|p| format!("priority[{p}]")));
+                    args.extend(prompt.map(|p| format!("prompt[{}]", p.trim())).filter(|p| p != "prompt[]"));
+                    call("dialogue", String::new(), args, Vec::new())
+                }),
+            (requirements, inner).prop_map(move |(r, o)| call("if", String::new(), vec![r.to_string(), o], Vec::new())),
+        ]
+    }).boxed()
+}
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn scenes_print_as_they_parse(
+        scenes in vec((arb_transform(), vec(arb_object(), 0..5)), 1..3)
+    ) {
+        let source: Vec<String> = scenes.into_iter().enumerate().map(|(i, (camera, objects))|
+            format!("#scene{i}[camera[{}], {}]", camera.join(", "), objects.join(",\n"))
+        ).collect();
+        let scenes = source.join("\n").parse::<GameScenes>();
+        // unnamed pickups in the same place can't be told apart, so they're rejected
+        let duplicate = |e: &ParseErr| e.locations().iter().any(|l| matches!(l.err, ParseErr::DuplicatePickup(_)));
+        prop_assume!(scenes.as_ref().err().is_none_or(|e| !duplicate(e)));
+        let scenes = scenes.unwrap();
+        let text = scenes.to_string();
+        let parsed: GameScenes = text.parse().unwrap();
+        prop_assert_eq!(&parsed, &scenes);
+        prop_assert_eq!(parsed.to_string(), text);
+    }
+}
@@ -1,15 +1,23 @@
-use std::str::FromStr;
-use crate::{file_types::*, PlayerData, GraphicsData};
+use std::{str::FromStr, fmt::Display};
+use crate::{file_types::*, PlayerData, GraphicsData, graphics::LoadingErr};
 pub struct ScriptReader {
     script_data: Option<ScriptReaderData>,
+    /// stats that went up from experience since [`take_level_ups`](Self::take_level_ups) was last called
+    level_ups: Vec<usize>,
+    /// an enemy from `$fight[...]` waiting for [`take_encounter`](Self::take_encounter)
+    encounter: Option<String>,
 }
 impl ScriptReader {
-    pub fn new() -> Self { Self { script_data: None, } }
+    pub fn new() -> Self { Self { script_data: None, level_ups: Vec::new(), encounter: None } }
+    pub fn take_level_ups(&mut self) -> Vec<usize> { std::mem::take(&mut self.level_ups) }
+    pub fn take_encounter(&mut self) -> Option<String> { self.encounter.take() }
     pub fn current_options(&self) -> Vec<&str> {
         let Some(segment) = self.current_segment() else { return Vec::new() };
         let mut options = Vec::new();
         for o in &segment.options { options.push(o.text.as_str()) }
-        let no_change = segment.add_tags.is_empty() && segment.remove_tags.is_empty();
+        let no_change = segment.add_tags.is_empty() && segment.remove_tags.is_empty()
+            && segment.stat_changes.is_empty() && segment.give_items.is_empty() && segment.take_items.is_empty()
+            && segment.affinity_changes.is_empty() && segment.xp_gains.is_empty() && segment.encounter.is_none();
         if !self.script_data.as_ref().unwrap().option_index.is_empty() && no_change {
             options.push("[Go Back]");
         }
@@ -32,6 +40,14 @@ impl ScriptReader {
     }
     pub fn next(&mut self, selection: usize, data: &mut PlayerData) -> Option<()> {
         data.acquired_tags.append(&mut self.current_segment()?.add_tags.clone());
+        for (stat, amount) in self.current_segment()?.stat_changes.clone() { data.change_stat(stat, amount) }
+        for (item, amount) in self.current_segment()?.give_items.clone() { data.give_item(&item, amount) }
+        for (item, amount) in self.current_segment()?.take_items.clone() { data.take_item(&item, amount) }
+        for (npc, amount) in self.current_segment()?.affinity_changes.clone() { data.change_affinity(&npc, amount) }
+        for (stat, amount) in self.current_segment()?.xp_gains.clone() {
+            if data.gain_xp(stat, amount) > 0 { self.level_ups.push(stat) }
+        }
+        if let Some(enemy) = &self.current_segment()?.encounter { self.encounter = Some(enemy.clone()) }
         if !self.current_options().is_empty() {
             // go back option
             if self.current_segment().as_ref()?.options.len() == selection {
@@ -58,93 +74,250 @@ impl ScriptReader {
         }
         Some(())
     }
-    pub fn set_script(&mut self, name: &str, player_data: &mut PlayerData, scene_loader: &mut GraphicsData) {
-        let script = scene_loader.scripts[name].clone();
+    /// starts a script unless it has already been read. `owner` is the name of the
+    /// object the script belongs to, which `self` in the script refers to
+    pub fn set_script(
+        &mut self, name: &str, owner: &str, player_data: &mut PlayerData, scene_loader: &mut GraphicsData
+    ) {
+        if player_data.read_scripts.iter().any(|s| s == name) { return }
+        self.replay_script(name, owner, player_data, scene_loader)
+    }
+    /// starts a script even if it has already been read
+    pub fn replay_script(
+        &mut self, name: &str, owner: &str, player_data: &mut PlayerData, scene_loader: &mut GraphicsData
+    ) {
+        let script = scene_loader.scripts[name].with_owner(owner);
         let name = name.to_string();
-        if player_data.read_scripts.contains(&name) { return }
-        player_data.read_scripts.push(name);
-        self.script_data = Some(ScriptReaderData { script, index: 0, option_index: Vec::new() })
+        if !player_data.read_scripts.contains(&name) { player_data.read_scripts.push(name.clone()) }
+        self.script_data = Some(ScriptReaderData {
+            path: name, owner: owner.to_string(), script, index: 0, option_index: Vec::new()
+        })
+    }
+    /// where the reader is up to, so it can be saved and resumed later
+    pub fn state(&self) -> Option<ReaderState> {
+        let ScriptReaderData { path, owner, index, option_index, .. } = self.script_data.as_ref()?;
+        Some(ReaderState {
+            script_path: path.clone(), owner: owner.clone(), index: *index, option_index: option_index.clone()
+        })
+    }
+    /// resumes reading from a saved state, loading the script if it isn't already
+    pub fn restore(&mut self, state: &ReaderState, scene_loader: &mut GraphicsData) -> Result<(), LoadingErr> {
+        scene_loader.load_script_file(&state.script_path)?;
+        let script = scene_loader.scripts[&state.script_path].with_owner(&state.owner);
+        let invalid = || LoadingErr::InvalidDialogueState(state.script_path.clone());
+        let mut segment = script.segments.get(state.index).ok_or_else(invalid)?;
+        for i in &state.option_index { segment = segment.options.get(*i).ok_or_else(invalid)? }
+        self.script_data = Some(ScriptReaderData {
+            path: state.script_path.clone(),
+            owner: state.owner.clone(),
+            script,
+            index: state.index,
+            option_index: state.option_index.clone(),
+        });
+        Ok(())
     }
 }
 struct ScriptReaderData {
+    path: String,
+    owner: String,
     script: Script,
     index: usize,
     option_index: Vec<usize>,
 }
-#[derive(Debug, PartialEq, Clone)]
+/// the position of a [`ScriptReader`], written as `reader[script_path, index, options[i, j], owner[poca]]`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReaderState {
+    pub script_path: String,
+    pub owner: String,
+    pub index: usize,
+    pub option_index: Vec<usize>,
+}
+impl Display for ReaderState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options: Vec<String> = self.option_index.iter().map(|i| i.to_string()).collect();
+        write!(
+            f, "reader[{}, {}, options[{}], owner[{}]]",
+            self.script_path, self.index, options.join(", "), self.owner
+        )
+    }
+}
+impl FromStr for ReaderState {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing reader state: {s}") }
+        let (prefix, inner) = split_call(s)?;
+        if prefix != "reader" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        // saves from before scripts had owners have no `owner[...]`
+        let (script_path, index, options, owner) = match split_args(&inner)?.as_slice() {
+            [script_path, index, options] => (script_path.clone(), index.clone(), options.clone(), String::new()),
+            [script_path, index, options, owner] => match split_call(owner)? {
+                ("owner", owner) => (script_path.clone(), index.clone(), options.clone(), owner.trim().to_string()),
+                (prefix, _) => return Err(ParseErr::InvalidPrefix(prefix.to_string())),
+            },
+            [_, _, _, _, _, ..] => return Err(ParseErr::ToManyArgs),
+            _ => return Err(ParseErr::NotEnoughArgs),
+        };
+        let (prefix, options) = split_call(&options)?;
+        if prefix != "options" { return Err(ParseErr::InvalidPrefix(prefix.to_string())) }
+        let mut option_index = Vec::new();
+        for i in split_args(&options)? { option_index.push(parse_index(&i)?) }
+        Ok(Self { script_path, owner, index: parse_index(&index)?, option_index })
+    }
+}
+fn parse_index(s: &str) -> Result<usize, ParseErr> {
+    s.trim().parse().map_err(|_| ParseErr::InvalidIndex(s.to_string()))
+}
+/// `.ron` and `.json` scripts hold the same things, see [`Format`]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Script {
     pub segments: Vec<Segment>
 }
 impl Script {
     pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, ParseErr> {
-        std::io::read_to_string(std::fs::File::open(path)?)?.parse()
+        let path = path.as_ref();
+        Format::of(path).parse(&std::fs::read_to_string(path)?)
+    }
+}
+impl Script {
+    /// a copy of the script with `self` replaced by the name of the object that owns it
+    pub fn with_owner(&self, owner: &str) -> Self {
+        let mut script = self.clone();
+        for segment in &mut script.segments { segment.resolve_self(owner) }
+        script
+    }
+}
+impl Display for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.segments { writeln!(f, "{}", segment.to_text(0))? }
+        Ok(())
     }
 }
 impl FromStr for Script {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self { segments: split_segments(s)? })
+        Ok(Self { segments: split_segments(&lexer::strip_comments(s))? })
     }
 }
-#[derive(Debug, PartialEq, Clone)]
+/// in `.ron` and `.json` only `text` has to be given, stats are written by name
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Segment {
     pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub options: Vec<Segment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub add_tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub remove_tags: Vec<String>,
+    /// changes to the player's own stat values, from `$stat[deception +1 expression -1]`
+    #[serde(skip_serializing_if = "Vec::is_empty", with = "stat_amounts")]
+    pub stat_changes: Vec<(usize, i32)>,
+    /// items and how many of each, from `$give[coin 3]`
+    #[serde(skip_serializing_if = "Vec::is_empty", deserialize_with = "known_items")]
+    pub give_items: Vec<(String, u32)>,
+    #[serde(skip_serializing_if = "Vec::is_empty", deserialize_with = "known_items")]
+    pub take_items: Vec<(String, u32)>,
+    /// changes to how much NPCs like the player, from `$affinity[poca +1 self -1]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub affinity_changes: Vec<(String, i32)>,
+    /// experience towards stats, from `$xp[fitness 10]`
+    #[serde(skip_serializing_if = "Vec::is_empty", with = "stat_amounts")]
+    pub xp_gains: Vec<(usize, u32)>,
+    /// an enemy to start an encounter with, from `$fight[guard]`
+    #[serde(skip_serializing_if = "Option::is_none", deserialize_with = "known_enemy")]
+    pub encounter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub requirements: Option<Requirements>,
 }
+/// stat amounts written as `[name, amount]` pairs in `.ron` and `.json`
+mod stat_amounts {
+    use serde::{Serialize, Deserialize, Serializer, Deserializer, de::Error};
+    use crate::file_types::{ParseErr, stats::stat_schema};
+    pub fn serialize<S: Serializer, T: Serialize>(amounts: &[(usize, T)], s: S) -> Result<S::Ok, S::Error> {
+        let schema = stat_schema();
+        s.collect_seq(amounts.iter().map(|(stat, amount)| (&schema.get(*stat).name, amount)))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(d: D) -> Result<Vec<(usize, T)>, D::Error> {
+        Vec::<(String, T)>::deserialize(d)?.into_iter().map(|(stat, amount)| match stat_schema().find(&stat) {
+            Some(stat) => Ok((stat, amount)),
+            None => Err(D::Error::custom(ParseErr::InvalidStat(stat))),
+        }).collect()
+    }
+}
+fn known_items<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<(String, u32)>, D::Error> {
+    Vec::<(String, u32)>::deserialize(d)?.into_iter()
+        .map(|(item, n)| Ok((items::item_catalogue().check(&item).map_err(serde::de::Error::custom)?, n)))
+        .collect()
+}
+fn known_enemy<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|enemy| enemies::enemy_catalogue().check(&enemy).map_err(serde::de::Error::custom))
+        .transpose()
+}
+impl Segment {
+    fn resolve_self(&mut self, owner: &str) {
+        for (npc, _) in &mut self.affinity_changes { if npc == "self" { *npc = owner.to_string() } }
+        if let Some(requirements) = &mut self.requirements { requirements.resolve_self(owner) }
+        for option in &mut self.options { option.resolve_self(owner) }
+    }
+}
+impl Segment {
+    /// the segment on one line with its control words after its text and right next to each other,
+    /// so they stay in the one segment. options go on their own lines indented one more than `depth`
+    fn to_text(&self, depth: usize) -> String {
+        let schema = stats::stat_schema();
+        let mut words = Vec::new();
+        if let Some(requirements) = &self.requirements { words.push(format!("$req[{requirements}]")) }
+        if !self.add_tags.is_empty()    { words.push(format!("$add[{}]", self.add_tags.join(" "))) }
+        if !self.remove_tags.is_empty() { words.push(format!("$rem[{}]", self.remove_tags.join(" "))) }
+        let mut push = |control: &str, changes: Vec<String>| if !changes.is_empty() {
+            words.push(format!("${control}[{}]", changes.join(" ")))
+        };
+        push("stat", self.stat_changes.iter().map(|(stat, n)| format!("{} {n:+}", schema.get(*stat).name)).collect());
+        push("give", self.give_items.iter().map(|(item, n)| format!("{item} {n}")).collect());
+        push("take", self.take_items.iter().map(|(item, n)| format!("{item} {n}")).collect());
+        push("affinity", self.affinity_changes.iter().map(|(npc, n)| format!("{npc} {n:+}")).collect());
+        push("xp", self.xp_gains.iter().map(|(stat, n)| format!("{} {n}", schema.get(*stat).name)).collect());
+        if let Some(enemy) = &self.encounter { words.push(format!("$fight[{enemy}]")) }
+        if !self.options.is_empty() {
+            let mut options = String::from("$opt[");
+            for option in &self.options { options += &format!("\n{}{}", "\t".repeat(depth + 1), option.to_text(depth + 1)) }
+            words.push(options + "\n" + &"\t".repeat(depth) + "]");
+        }
+        if self.text.is_empty() { words.concat() } else { lexer::escape(&self.text) + " " + &words.concat() }
+    }
+}
 impl FromStr for Segment {
     type Err = ParseErr;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if debug_parse() { println!("parsing segment: {s}") }
+        let mut segment = Segment::default();
         let mut word = String::new();
         let mut last_was_control = false;
-        let mut skip = 0;
-
+        // byte offset the end of the last control word's brackets
+        let mut skip_to = 0;
         let mut text = String::new();
-        let mut requirements = None;
-        let mut add_tags    = Vec::new();
-        let mut remove_tags = Vec::new();
-        let mut options     = Vec::new();
 
-        for (i, c) in s.chars().enumerate() {
-            if skip != 0 { skip -= 1; continue }
+        for (i, c) in s.char_indices() {
+            if i < skip_to { continue }
             if c.is_whitespace() {
-                if !last_was_control { text += &word; word = String::new() } 
-                else { last_was_control = false }
+                if !last_was_control { text += &word } 
+                last_was_control = false;
+                word = String::new();
+            }
+            if let Some((control, apply)) = CONTROLS.iter().find(|(w, _)| c == '[' && *w == word.trim()) {
+                let context = |e: ParseErr, span| e.context(format!("`{control}`"), span);
+                let (inner, end) = split_bracket(&s[(i+1)..]).map_err(|e| context(e, i + 1..s.len()))?;
+                // errors point at the inside of the control word's brackets
+                apply(&mut segment, &inner).map_err(|e| context(e, i + 1..i + end))?;
+                skip_to = i + 1 + end;
+                last_was_control = true;
+                word = String::new();
+                continue
             }
-            if c == '[' { match word.trim() {
-                "$req" => {
-                    let (inner, temp_skip) = split_bracket(&s[(i+1)..])?;
-                    skip = temp_skip;
-                    requirements = Some(inner.parse()?);
-                    last_was_control = true;
-                },
-                "$add" => {
-                    let (inner, temp_skip) = split_bracket(&s[(i+1)..])?;
-                    skip = temp_skip;
-                    let mut adding = inner.split_whitespace().map(|i| i.trim().to_string()).collect();
-                    add_tags.append(&mut adding);
-                    last_was_control = true;
-                },
-                "$rem" => {
-                    let (inner, temp_skip) = split_bracket(&s[(i+1)..])?;
-                    skip = temp_skip;
-                    let mut removing = inner.split_whitespace().map(|i| i.trim().to_string()).collect();
-                    remove_tags.append(&mut removing);
-                    last_was_control = true;
-                },
-                "$opt" => {
-                    let (inner, temp_skip) = split_bracket(&s[(i+1)..])?;
-                    skip = temp_skip;
-                    options.append(&mut split_segments(&inner)?);
-                    last_was_control = true;
-                },
-                _ => ()
-            } }
             word.push(c);
         }
+        if !last_was_control { text += &word }
         let mut parsed_text = String::new();
         let mut last_was_control = false;
         let mut trimmed = false;
@@ -154,47 +327,217 @@ impl FromStr for Segment {
             if last_was_control || (!trimmed && c.is_whitespace()) { continue }
             parsed_text.push(c);
         }
-        Ok(Self {
-            requirements,
-            text: parsed_text,
-            add_tags,
-            remove_tags,
-            options,
-        })
+        segment.text = parsed_text.trim_end().to_string();
+        Ok(segment)
+    }
+}
+/// what a control word does to the segment it's in, given the inside of its brackets
+type Control = fn(&mut Segment, &str) -> Result<(), ParseErr>;
+/// every control word a segment can have
+pub const CONTROLS: &[(&str, Control)] = &[
+    ("$req",      |s, inner| { s.requirements = Some(inner.parse()?); Ok(()) }),
+    ("$add",      |s, inner| { s.add_tags.extend(inner.split_whitespace().map(str::to_string)); Ok(()) }),
+    ("$rem",      |s, inner| { s.remove_tags.extend(inner.split_whitespace().map(str::to_string)); Ok(()) }),
+    ("$stat",     |s, inner| { s.stat_changes.append(&mut parse_stat_changes(inner)?); Ok(()) }),
+    ("$give",     |s, inner| { s.give_items.append(&mut items::parse_item_changes(inner)?); Ok(()) }),
+    ("$take",     |s, inner| { s.take_items.append(&mut items::parse_item_changes(inner)?); Ok(()) }),
+    ("$affinity", |s, inner| { s.affinity_changes.append(&mut parse_affinity_changes(inner)?); Ok(()) }),
+    ("$xp",       |s, inner| { s.xp_gains.append(&mut parse_xp_gains(inner)?); Ok(()) }),
+    ("$fight",    |s, inner| { s.encounter = Some(enemies::enemy_catalogue().check(inner.trim())?); Ok(()) }),
+    ("$opt",      |s, inner| { s.options.append(&mut split_segments(inner)?); Ok(()) }),
+];
+fn parse_stat_changes(s: &str) -> Result<Vec<(usize, i32)>, ParseErr> {
+    if debug_parse() { println!("parsing stat changes: {s}") }
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let mut changes = Vec::new();
+    for pair in words.chunks(2) {
+        let [stat, amount] = pair else { return Err(ParseErr::NotEnoughArgs) };
+        let stat = stats::stat_schema().find(stat).ok_or_else(|| ParseErr::InvalidStat(stat.to_string()))?;
+        let amount = amount.parse().map_err(|_| ParseErr::InvalidIndex(amount.to_string()))?;
+        changes.push((stat, amount));
     }
+    Ok(changes)
+}
+/// experience for stats, from `$xp[...]` in scripts and `xp[...]` on quests
+pub fn parse_xp_gains(s: &str) -> Result<Vec<(usize, u32)>, ParseErr> {
+    if debug_parse() { println!("parsing xp gains: {s}") }
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let mut gains = Vec::new();
+    for pair in words.chunks(2) {
+        let [stat, amount] = pair else { return Err(ParseErr::NotEnoughArgs) };
+        let stat = stats::stat_schema().find(stat).ok_or_else(|| ParseErr::InvalidStat(stat.to_string()))?;
+        let amount = amount.parse().map_err(|_| ParseErr::InvalidIndex(amount.to_string()))?;
+        gains.push((stat, amount));
+    }
+    Ok(gains)
+}
+fn parse_affinity_changes(s: &str) -> Result<Vec<(String, i32)>, ParseErr> {
+    if debug_parse() { println!("parsing affinity changes: {s}") }
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let mut changes = Vec::new();
+    for pair in words.chunks(2) {
+        let [npc, amount] = pair else { return Err(ParseErr::NotEnoughArgs) };
+        let amount = amount.parse().map_err(|_| ParseErr::InvalidIndex(amount.to_string()))?;
+        changes.push((npc.to_string(), amount));
+    }
+    Ok(changes)
 }
-const CONTROL_WORDS: &[&str] = &["$req", "$add", "$rem", "$opt"];
 fn split_next_segment(s: &str) -> Result<(String, usize), ParseErr> {
     let mut segment = String::new();
     let mut word    = String::new();
     let mut segment_args_started = false;
-    let mut skip = 0;
-    for (i, c) in s.chars().enumerate() {
-        if skip != 0 { skip -= 1; continue }
+    let mut skip_to = 0;
+    for (i, c) in s.char_indices() {
+        if i < skip_to { continue }
         if c.is_whitespace() {
             word.push(c);
             segment += &word;
             word = String::new();
-            if segment_args_started && !CONTROL_WORDS.contains(&(word.clone()+"[").trim()) {
+            if segment_args_started {
                 return Ok((s[..i].to_string(), i))
             }
         }
-        if c == '[' && CONTROL_WORDS.contains(&word.trim()) {
+        if c == '[' && CONTROLS.iter().any(|(w, _)| *w == word.trim()) {
             segment_args_started = true;
-            (_, skip) = split_bracket(&s[(i+1)..])?;
+            let (_, end) = split_bracket(&s[(i+1)..])?;
+            skip_to = i + 1 + end;
+            // control words written right after it with no space are part of the same segment
+            word = String::new();
+            continue
         }
         word.push(c);
     }
     Ok((s.to_string(), s.len()))
 }
+/// splits and parses every segment, carrying on past any that fail so all their errors are given together
 fn split_segments(s: &str) -> Result<Vec<Segment>, ParseErr> {
     if debug_parse() { println!("splitting segments: {s}") }
     let mut segments = Vec::new();
-    let mut s = s.to_string();
+    let mut errs = Vec::new();
+    let mut offset = 0;
     loop {
-        let (segment, i) = split_next_segment(&s)?;
-        if !segment.trim().is_empty() { segments.push(segment.parse()?) }
-        if i == s.len() { return Ok(segments) }
-        s = s[i..].to_string();
+        let (segment, i) = split_next_segment(&s[offset..])?;
+        let trimmed = segment.trim_start();
+        if !trimmed.trim().is_empty() {
+            let start = offset + segment.len() - trimmed.len();
+            let span = start..start + trimmed.trim_end().len();
+            let name: String = trimmed.chars().take_while(|c| *c != '\n' && *c != '$').take(24).collect();
+            match trimmed.parse::<Segment>() {
+                Ok(segment) => segments.push(segment),
+                Err(e) => errs.push(e.context(format!("segment `{}`", name.trim()), span)),
+            }
+        }
+        offset += i;
+        if offset == s.len() { break }
+    }
+    match errs.len() {
+        0 => Ok(segments),
+        1 => Err(errs.remove(0)),
+        _ => Err(ParseErr::Many(errs)),
+    }
+}
+
+#[test]
+fn reader_state_round_trip() {
+    let state = ReaderState {
+        script_path: "test.scr".to_string(), owner: "poca".to_string(), index: 3, option_index: vec![0, 2]
+    };
+    assert_eq!(state.to_string().parse::<ReaderState>().unwrap(), state);
+    let state = ReaderState {
+        script_path: "test.scr".to_string(), owner: String::new(), index: 0, option_index: Vec::new()
+    };
+    assert_eq!(state.to_string().parse::<ReaderState>().unwrap(), state);
+    assert_eq!("reader[test.scr, 0, options[]]".parse::<ReaderState>().unwrap(), state);
+}
+#[test]
+fn unicode_dialogue() {
+    let script: Script = "Café — naïve? 🙂 $opt[Oui $add[oui] Non… $add[non]]\n# not dialogue\nÇa va $req[oui]"
+        .parse().unwrap();
+    assert_eq!(script.segments[0].text, "Café — naïve? 🙂");
+    assert_eq!(script.segments[0].options[1].text, "Non…");
+    assert_eq!(script.segments[1].text, "Ça va");
+    assert_eq!(script.segments.len(), 2);
+}
+#[test]
+fn lone_quotes_are_dialogue() {
+    let script: Script = "He said \"run $add[ran]\nShe said \"ok\" # aside\n$req[ran]".parse().unwrap();
+    assert_eq!(script.segments[0].text, "He said \"run");
+    assert_eq!(script.segments[1].text, "She said \"ok\"");
+}
+#[test]
+fn self_is_the_owner() {
+    let script: Script = "hi $affinity[self +2] $opt[yes $req[affinity[self] >= 2]]".parse().unwrap();
+    let script = script.with_owner("poca");
+    assert_eq!(script.segments[0].affinity_changes, [("poca".to_string(), 2)]);
+    let mut data = PlayerData::default();
+    data.change_affinity("poca", 2);
+    assert!(script.segments[1].options[0].requirements.as_ref().unwrap().evaluate(&data));
+}
+#[test]
+fn scripts_round_trip_through_ron_and_json() {
+    let script: Script = "Hello $stat[deception +1]$affinity[poca -2]\n\
+        Hows it going? $opt[\n\tFine $add[fine]$xp[fitness 10]\n\tBad $req[!fine & affinity[poca] < 0]$rem[fine]\n]"
+        .parse().unwrap();
+    assert_eq!((script.segments.len(), script.segments[1].options.len()), (2, 2));
+    for format in [Format::Ron, Format::Json] {
+        let text = format.write(&script).unwrap();
+        assert_eq!(format.parse::<Script>(&text).unwrap(), script, "{text}");
+    }
+    let err = Format::Json.parse::<Script>("{\"segments\": [{\"text\": \"hi\", \"stat_changes\": [[\"luck\", 1]]}]}").unwrap_err();
+    assert!(err.to_string().contains("unknown stat `luck`"), "{err}");
+}
+#[cfg(test)]
+use proptest::{prelude::*, collection::vec, option};
+#[cfg(test)]
+fn arb_requirements() -> impl Strategy<Value = Requirements> {
+    let tag = "[a-z][a-z_]{0,5}";
+    let comparison = prop::sample::select(vec![
+        Comparison::LessThan, Comparison::LessThanOrEqual, Comparison::GreaterThan,
+        Comparison::GreaterThanOrEqual, Comparison::Equal, Comparison::NotEqual,
+    ]);
+    let leaf = prop_oneof![
+        tag.prop_map(ReqVal::Tag),
+        tag.prop_map(|t| ReqVal::Not(Box::new(ReqVal::Tag(t)))),
+        (0..stats::stat_schema().len(), comparison.clone(), -50.0f32..50.0)
+            .prop_map(|(stat, c, v)| ReqVal::Comparison(CompVal::Stat(stat), c, CompVal::Const(v))),
+        ("[a-z]{1,5}", comparison, -5i32..5)
+            .prop_map(|(npc, c, v)| ReqVal::Comparison(CompVal::Affinity(npc), c, CompVal::Const(v as f32))),
+    ];
+    let ops = prop::sample::select(vec![ReqOps::And, ReqOps::Or]);
+    let group = move |val: BoxedStrategy<ReqVal>| (val.clone(), vec((ops.clone(), val), 0..3))
+        .prop_map(|(head, body)| Requirements { head, body });
+    let val = leaf.prop_recursive(2, 8, 3, move |inner| prop_oneof![
+        inner.clone(),
+        group(inner.clone().boxed()).prop_map(|r| ReqVal::Req(Box::new(r))),
+        inner.prop_map(|v| ReqVal::Not(Box::new(v))),
+    ]).boxed();
+    let ops = prop::sample::select(vec![ReqOps::And, ReqOps::Or]);
+    (val.clone(), vec((ops, val), 0..3)).prop_map(|(head, body)| Requirements { head, body })
+}
+/// segments that can be written down, every one has a control word so it can't run into the next
+#[cfg(test)]
+fn arb_segment() -> impl Strategy<Value = Segment> {
+    let tag = "[a-z][a-z_]{0,5}";
+    let stat = 0..stats::stat_schema().len();
+    let text = vec("[A-Za-zé?!.,'\\[\\]#\"\\\\]{1,6}", 0..5).prop_map(|words| words.join(" "));
+    let leaf = (
+        text, option::of(arb_requirements()), vec(tag, 1..3), vec(tag, 0..2),
+        vec((stat.clone(), -9i32..10), 0..3), vec(("[a-z]{1,5}", -9i32..10), 0..3), vec((stat, 0u32..100), 0..2),
+    ).prop_map(|(text, requirements, add_tags, remove_tags, stat_changes, affinity_changes, xp_gains)| Segment {
+        text, requirements, add_tags, remove_tags, stat_changes, affinity_changes, xp_gains,
+        give_items: Vec::new(), take_items: Vec::new(), encounter: None, options: Vec::new(),
+    });
+    leaf.prop_recursive(2, 8, 3, |inner| (inner.clone(), vec(inner, 1..3))
+        .prop_map(|(segment, options)| Segment { options, ..segment }))
+}
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn scripts_print_as_they_parse(segments in vec(arb_segment(), 1..4)) {
+        let script = Script { segments };
+        let text = script.to_string();
+        let parsed: Script = text.parse().unwrap();
+        prop_assert_eq!(&parsed, &script);
+        prop_assert_eq!(parsed.to_string(), text);
     }
 }
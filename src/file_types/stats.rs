@@ -0,0 +1,237 @@
+use std::{str::FromStr, sync::{Arc, LazyLock, RwLock}};
+use crate::file_types::*;
+
+pub const STATS_PATH: &str = "stats.sts";
+
+static SCHEMA: LazyLock<RwLock<Arc<StatSchema>>> = LazyLock::new(RwLock::default);
+/// the schema loaded by [`load_stat_schema`], or sword-ward's own stats if nothing was loaded
+pub fn stat_schema() -> Arc<StatSchema> { SCHEMA.read().unwrap().clone() }
+/// replaces the stats until they're next loaded, giving the ones they replaced
+pub fn set_stat_schema(schema: StatSchema) -> Arc<StatSchema> { std::mem::replace(&mut SCHEMA.write().unwrap(), Arc::new(schema)) }
+/// loads the stats the game uses, this has to happen before any scenes or scripts are parsed
+/// as stat names in them are resolved against it. a missing file keeps the default stats
+pub fn load_stat_schema(path: impl AsRef<std::path::Path>) -> Result<(), ParseErr> {
+    let schema = match std::fs::read_to_string(path) {
+        Ok(s) => s.parse()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => StatSchema::default(),
+        Err(e) => return Err(e.into()),
+    };
+    set_stat_schema(schema);
+    Ok(())
+}
+/// every stat in the game and the stat each is derived from.
+/// a stat's value is its `formula` worked out from its parent's value (0 for top level
+/// stats) and its own value, which starts at `start` and is what scripts change.
+/// each amount of experience in `xp` raises the stat's own value by one once it's reached.
+/// ```text
+/// recovery[start[1], focus, reasoning]
+/// charisma[
+///     start[1]
+///     xp[10, 30, 60]
+///     expression
+///     deception[formula[parent / 2 + modifier * 2]]
+/// ]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatSchema {
+    stats: Vec<StatDef>,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatDef {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub start: i32,
+    /// `None` is `parent + modifier`
+    pub formula: Option<Formula>,
+    /// total experience needed for each level, in increasing order
+    pub thresholds: Vec<u32>,
+}
+impl StatSchema {
+    pub fn find(&self, name: &str) -> Option<usize> { self.stats.iter().position(|s| s.name == name) }
+    pub fn get(&self, stat: usize) -> &StatDef { &self.stats[stat] }
+    pub fn len(&self) -> usize { self.stats.len() }
+    /// how many stats this one is below
+    pub fn depth(&self, stat: usize) -> usize {
+        self.stats[stat].parent.map(|p| self.depth(p) + 1).unwrap_or(0)
+    }
+    /// works out a stat from the player's own values for it and everything above it
+    pub fn evaluate(&self, stat: usize, own_values: &[i32]) -> f32 {
+        let def = &self.stats[stat];
+        let parent = def.parent.map(|p| self.evaluate(p, own_values)).unwrap_or(0.0);
+        let modifier = own_values.get(stat).copied().unwrap_or(0) as f32;
+        match &def.formula {
+            Some(formula) => formula.evaluate(parent, modifier),
+            None => parent + modifier,
+        }
+    }
+    /// how many levels of a stat the experience is worth
+    pub fn levels(&self, stat: usize, xp: u32) -> i32 {
+        self.stats[stat].thresholds.iter().filter(|t| **t <= xp).count() as i32
+    }
+    /// what a new player's own value for each stat is
+    pub fn start_values(&self) -> Vec<i32> { self.stats.iter().map(|s| s.start).collect() }
+    fn parse_stat(&mut self, s: &str, parent: Option<usize>) -> Result<(), ParseErr> {
+        if debug_parse() { println!("parsing stat: {s}") }
+        let (name, args) = if s.contains('[') { split_call(s)? } else { (s.trim(), String::new()) };
+        if self.find(name).is_some() { return Err(ParseErr::DuplicateStat(name.to_string())) }
+        let id = self.stats.len();
+        self.stats.push(StatDef { name: name.to_string(), parent, start: 0, formula: None, thresholds: Vec::new() });
+        for arg in split_args(&args)? {
+            if !arg.contains('[') { self.parse_stat(&arg, Some(id))?; continue }
+            match split_call(&arg)? {
+                ("start", v)   => self.stats[id].start = v.trim().parse()
+                    .map_err(|_| ParseErr::InvalidIndex(v.clone()))?,
+                ("formula", f) => self.stats[id].formula = Some(f.parse()?),
+                ("xp", t) => {
+                    let mut thresholds = Vec::new();
+                    for t in split_args(&t)? {
+                        thresholds.push(t.trim().parse().map_err(|_| ParseErr::InvalidIndex(t.clone()))?)
+                    }
+                    if !thresholds.is_sorted() { return Err(ParseErr::InvalidIndex(t)) }
+                    self.stats[id].thresholds = thresholds;
+                },
+                _ => self.parse_stat(&arg, Some(id))?,
+            }
+        }
+        Ok(())
+    }
+}
+impl Default for StatSchema {
+    fn default() -> Self {
+        "recovery[start[1], focus, reasoning]
+        fitness[start[1], strength, speed]
+        charisma[start[1], expression, deception]".parse().unwrap()
+    }
+}
+impl FromStr for StatSchema {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing stat schema: {s}") }
+        let mut schema = Self { stats: Vec::new() };
+        let mut errs = Vec::new();
+        for (arg, span) in split_args_spanned(s)? {
+            if let Err(e) = schema.parse_stat(&arg, None) { errs.push(e.context(format!("stat `{}`", describe(&arg)), span)) }
+        }
+        match errs.len() {
+            0 => Ok(schema),
+            1 => Err(errs.remove(0)),
+            _ => Err(ParseErr::Many(errs)),
+        }
+    }
+}
+/// arithmetic over a sub-stat's `parent` value and its `modifier`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Const(f32),
+    Parent,
+    Modifier,
+    Neg(Box<Formula>),
+    Add(Box<Formula>, Box<Formula>),
+    Sub(Box<Formula>, Box<Formula>),
+    Mul(Box<Formula>, Box<Formula>),
+    Div(Box<Formula>, Box<Formula>),
+}
+impl Formula {
+    pub fn evaluate(&self, parent: f32, modifier: f32) -> f32 {
+        match self {
+            Self::Const(v) => *v,
+            Self::Parent   => parent,
+            Self::Modifier => modifier,
+            Self::Neg(a)    => -a.evaluate(parent, modifier),
+            Self::Add(a, b) => a.evaluate(parent, modifier) + b.evaluate(parent, modifier),
+            Self::Sub(a, b) => a.evaluate(parent, modifier) - b.evaluate(parent, modifier),
+            Self::Mul(a, b) => a.evaluate(parent, modifier) * b.evaluate(parent, modifier),
+            Self::Div(a, b) => a.evaluate(parent, modifier) / b.evaluate(parent, modifier),
+        }
+    }
+}
+impl FromStr for Formula {
+    type Err = ParseErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if debug_parse() { println!("parsing formula: {s}") }
+        let tokens = formula_tokens(s)?;
+        let (formula, rest) = parse_sum(&tokens)?;
+        if !rest.is_empty() { return Err(ParseErr::InvalidFormula(s.to_string())) }
+        Ok(formula)
+    }
+}
+fn formula_tokens(s: &str) -> Result<Vec<String>, ParseErr> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in s.chars() {
+        if c.is_alphanumeric() || c == '.' || c == '_' { word.push(c); continue }
+        if !word.is_empty() { tokens.push(std::mem::take(&mut word)) }
+        match c {
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(c.to_string()),
+            c if c.is_whitespace() => (),
+            _ => return Err(ParseErr::InvalidFormula(s.to_string())),
+        }
+    }
+    if !word.is_empty() { tokens.push(word) }
+    Ok(tokens)
+}
+fn parse_sum(tokens: &[String]) -> Result<(Formula, &[String]), ParseErr> {
+    let (mut result, mut tokens) = parse_product(tokens)?;
+    while let [op, rest @ ..] = tokens {
+        let (rhs, rest) = match op.as_str() { "+" | "-" => parse_product(rest)?, _ => break };
+        result = if op == "+" { Formula::Add(Box::new(result), Box::new(rhs)) }
+        else { Formula::Sub(Box::new(result), Box::new(rhs)) };
+        tokens = rest;
+    }
+    Ok((result, tokens))
+}
+fn parse_product(tokens: &[String]) -> Result<(Formula, &[String]), ParseErr> {
+    let (mut result, mut tokens) = parse_term(tokens)?;
+    while let [op, rest @ ..] = tokens {
+        let (rhs, rest) = match op.as_str() { "*" | "/" => parse_term(rest)?, _ => break };
+        result = if op == "*" { Formula::Mul(Box::new(result), Box::new(rhs)) }
+        else { Formula::Div(Box::new(result), Box::new(rhs)) };
+        tokens = rest;
+    }
+    Ok((result, tokens))
+}
+fn parse_term(tokens: &[String]) -> Result<(Formula, &[String]), ParseErr> {
+    match tokens {
+        [t, rest @ ..] if t == "-" => {
+            let (inner, rest) = parse_term(rest)?;
+            Ok((Formula::Neg(Box::new(inner)), rest))
+        },
+        [t, rest @ ..] if t == "(" => match parse_sum(rest)? {
+            (inner, [close, rest @ ..]) if close == ")" => Ok((inner, rest)),
+            _ => Err(ParseErr::NoCloseBracket),
+        },
+        [t, rest @ ..] => Ok((match t.as_str() {
+            "parent"   => Formula::Parent,
+            "modifier" => Formula::Modifier,
+            t => Formula::Const(t.parse().map_err(|_| ParseErr::InvalidFormula(t.to_string()))?),
+        }, rest)),
+        [] => Err(ParseErr::NotEnoughArgs),
+    }
+}
+
+#[test]
+fn stat_hierarchy() {
+    let schema: StatSchema = "charisma[start[2], expression, deception[formula[parent / 2 + modifier * 2]]]"
+        .parse().unwrap();
+    let mut values = schema.start_values();
+    assert_eq!(values, [2, 0, 0]);
+    values[schema.find("deception").unwrap()] = 3;
+    assert_eq!(schema.evaluate(schema.find("expression").unwrap(), &values), 2.0);
+    assert_eq!(schema.evaluate(schema.find("deception").unwrap(),  &values), 7.0);
+    assert!("charisma[deception, deception]".parse::<StatSchema>().is_err());
+}
+#[test]
+fn xp_thresholds() {
+    let schema: StatSchema = "fitness[xp[10, 30], speed]".parse().unwrap();
+    assert_eq!(schema.levels(0, 9), 0);
+    assert_eq!(schema.levels(0, 30), 2);
+    assert_eq!(schema.levels(1, 100), 0);
+    assert!("fitness[xp[30, 10]]".parse::<StatSchema>().is_err());
+}
+#[test]
+fn formula_precedence() {
+    let formula: Formula = "parent / 2 + modifier * (1 - -1)".parse().unwrap();
+    assert_eq!(formula.evaluate(4.0, 3.0), 8.0);
+    assert!("parent +".parse::<Formula>().is_err());
+    assert!("parent ^ 2".parse::<Formula>().is_err());
+}
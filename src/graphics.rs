@@ -1,7 +1,7 @@
 use crate::INT_SCALE;
 use thin_engine::{
     glium::texture::*, Display,
-    prelude::*, text_renderer::Font,
+    prelude::*, text_renderer::{Font, TextRenderer, TextDrawError},
     glium::{DrawError, ProgramCreationError, vertex::BufferCreationError, uniforms::UniformBuffer, implement_uniform_block}
 };
 use std::{path::Path, collections::HashMap, fs::read_to_string};
@@ -49,11 +49,11 @@ pub fn lighting_from_scene(scene: &Scene, data: &PlayerData) -> (PointLights, Di
     
     for (o, t) in scene.all_valid_objects(data) { match o.object_type {
         ObjectType::PointLight { strength, colour } => {
-            point_lights[p_i] = PointLight::new(t, colour, strength);
+            point_lights[p_i] = PointLight::new(t, colour.scale(1.0 / 255.0), strength);
             p_i += 1
         },
         ObjectType::DirLight   { strength, colour } => {
-            dir_lights[d_i] = DirLight::new(t.into(), colour, strength);
+            dir_lights[d_i] = DirLight::new(t.into(), colour.scale(1.0 / 255.0), strength);
             d_i += 1
         },
         _ => ()
@@ -81,9 +81,21 @@ pub enum DrawInfo<'a> {
 pub enum LoadDrawError {
     LoadingErr(LoadingErr),
     DrawErr(DrawError),
+    TextDrawError(TextDrawError),
 }
 impl From<DrawError>  for LoadDrawError { fn from(e: DrawError)  -> Self { Self::DrawErr(e)    } }
+impl From<TextDrawError> for LoadDrawError { fn from(e: TextDrawError) -> Self { Self::TextDrawError(e) } }
 impl From<LoadingErr> for LoadDrawError { fn from(e: LoadingErr) -> Self { Self::LoadingErr(e) } }
+impl std::fmt::Display for LoadDrawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LoadingErr(e)    => write!(f, "{e}"),
+            Self::DrawErr(e)       => write!(f, "{e}"),
+            Self::TextDrawError(TextDrawError::GliumDrawError(e)) => write!(f, "{e}"),
+            Self::TextDrawError(TextDrawError::NoNewLineData) => write!(f, "the font has no line height data"),
+        }
+    }
+}
 pub struct GraphicsData<'a> {
     pub images:  HashMap<String, Texture2d>,
     pub meshes:  HashMap<String, Vec<Mesh>>,
@@ -102,27 +114,11 @@ pub struct GraphicsData<'a> {
 impl GraphicsData<'_> {
     pub fn new(display: &thin_engine::Display) -> Result<Self, LoadingErr> {
         use draw_parameters::*;
+        let (image_shader, shader, debug_shader) = load_shaders(display)?;
         let mut result = Self {
             font: Font::from_scale_and_file(INT_SCALE as f32 * 0.1, "FantasqueSansMono-Regular.ttf")?,
             image_mesh: Mesh::image_mesh(display)?,
-            image_shader: Program::from_source(
-                display,
-                thin_engine::shaders::VERTEX,
-                &read_to_string("shaders/image_fs.glsl")?,
-                None,
-            )?,
-            shader: Program::from_source(
-                display,
-                &read_to_string("shaders/shaded_vs.glsl")?,
-                &read_to_string("shaders/shaded_fs.glsl")?,
-                None,
-            )?,
-            debug_shader: Program::from_source(
-                display,
-                thin_engine::shaders::VERTEX,
-                &read_to_string("shaders/debug_fs.glsl")?,
-                None
-            )?,
+            image_shader, shader, debug_shader,
             text_mesh:  Mesh::text_mesh( display)?,
             text_shader: Font::shader(display)?,
             scripts: HashMap::new(),
@@ -256,6 +252,42 @@ impl GraphicsData<'_> {
 
         Ok(())
     }
+    /// draws a line of text in screen space, where the screen goes from -4/3 to 4/3 across
+    pub fn draw_text(
+        &mut self, frame: &mut impl Surface, display: &Display, text: &str, colour: Vec3, pos: Vec2
+    ) -> Result<(), TextDrawError> {
+        TextRenderer {
+            shader:      &self.text_shader,
+            indices:     &self.text_mesh.indices,
+            vertices:    &self.text_mesh.vertices,
+            uvs:         &self.text_mesh.uvs,
+            draw_params: &self.text_params,
+            display
+        }.draw(
+            text, colour, frame,
+            Mat4::from_pos_and_scale(pos.extend(0.0), Vec3::splat(0.1)),
+            Mat4::view_matrix_2d((4, 3)), Mat4::default(),
+            &mut self.font
+        )
+    }
+    /// draws an image in screen space centred on `pos`, loading it if it isn't already
+    pub fn draw_image(
+        &mut self, frame: &mut impl Surface, display: &Display, path: &str, pos: Vec2, scale: f32
+    ) -> Result<(), LoadDrawError> {
+        self.load_image_file(path, display)?;
+        frame.draw(
+            (&self.image_mesh.vertices, &self.image_mesh.uvs),
+            &self.image_mesh.indices,
+            &self.image_shader, &uniform! {
+                tex: self.images[path].sampled().magnify_filter(MagnifySamplerFilter::Nearest),
+                camera: Mat4::default(),
+                model: Mat4::from_pos_and_scale(pos.extend(0.0), Vec3::splat(scale)),
+                view: Mat4::view_matrix_2d((4, 3)),
+            },
+            &self.text_params,
+        )?;
+        Ok(())
+    }
     pub fn load_mesh_file( &mut self, path: &str, display: &Display) -> Result<(), LoadingErr> {
         if self.meshes.contains_key(path) { return Ok(()) }
         self.meshes.insert(path.to_string(), Mesh::from_file(path, display)?);
@@ -263,12 +295,7 @@ impl GraphicsData<'_> {
     }
     pub fn load_image_file(&mut self, path: &str, display: &Display) -> Result<(), LoadingErr> {
         if self.images.contains_key(path) { return Ok(()) }
-        let image = image::ImageReader::open(path)?.decode()?.to_rgba8();
-        let size = image.dimensions();
-        let data = image.into_vec();
-        let raw = glium::texture::RawImage2d::from_raw_rgba_reversed(&data, size);
-        let texture = Texture2d::new(display, raw)?;
-        self.images.insert(path.to_string(), texture);
+        self.images.insert(path.to_string(), read_image(path, display)?);
         Ok(())
     }
     pub fn load_script_file(&mut self, path: &str) -> Result<(), LoadingErr> {
@@ -276,6 +303,29 @@ impl GraphicsData<'_> {
         self.scripts.insert(path.to_string(), script::Script::from_file(path)?);
         Ok(())
     }
+    /// loads every image, mesh and script that came from `path` again,
+    /// leaving them all as they were if any of them fail
+    pub fn reload_file(&mut self, path: &Path, display: &Display) -> Result<(), LoadingErr> {
+        let from_path = |key: &str| Path::new(key).canonicalize().is_ok_and(|k| k == path);
+        let images: Vec<String> = self.images.keys().filter(|k| from_path(k)).cloned().collect();
+        let meshes: Vec<String> = self.meshes.keys()
+            .filter(|k| from_path(gltf_import::split_mesh_path(k).map_or(k, |(file, _)| file)))
+            .cloned().collect();
+        let scripts: Vec<String> = self.scripts.keys().filter(|k| from_path(k)).cloned().collect();
+
+        let images = images.into_iter().map(|k| Ok((read_image(&k, display)?, k))).collect::<Result<Vec<_>, LoadingErr>>()?;
+        let meshes = meshes.into_iter().map(|k| Ok((Mesh::from_file(&k, display)?, k))).collect::<Result<Vec<_>, LoadingErr>>()?;
+        let scripts = scripts.into_iter().map(|k| Ok((script::Script::from_file(&k)?, k))).collect::<Result<Vec<_>, LoadingErr>>()?;
+        for (image, k)  in images  { self.images.insert(k, image); }
+        for (mesh, k)   in meshes  { self.meshes.insert(k, mesh); }
+        for (script, k) in scripts { self.scripts.insert(k, script); }
+        Ok(())
+    }
+    /// compiles the shaders in [`SHADERS_PATH`] again, keeping the old ones if any fail
+    pub fn reload_shaders(&mut self, display: &Display) -> Result<(), LoadingErr> {
+        (self.image_shader, self.shader, self.debug_shader) = load_shaders(display)?;
+        Ok(())
+    }
     pub fn load_scene(&mut self, scene: &Scene, display: &Display) -> Result<(), LoadingErr> {
         for image in scene.image_paths() { self.load_image_file(&image, display)? }
         for mesh  in scene.mesh_paths()  { self.load_mesh_file( &mesh,  display)? }
@@ -286,6 +336,23 @@ impl GraphicsData<'_> {
         Ok(())
     }
 }
+pub const SHADERS_PATH: &str = "shaders";
+/// the image, shaded and debug shaders
+fn load_shaders(display: &Display) -> Result<(Program, Program, Program), LoadingErr> {
+    let read = |name: &str| read_to_string(Path::new(SHADERS_PATH).join(name));
+    Ok((
+        Program::from_source(display, thin_engine::shaders::VERTEX, &read("image_fs.glsl")?, None)?,
+        Program::from_source(display, &read("shaded_vs.glsl")?, &read("shaded_fs.glsl")?, None)?,
+        Program::from_source(display, thin_engine::shaders::VERTEX, &read("debug_fs.glsl")?, None)?,
+    ))
+}
+fn read_image(path: &str, display: &Display) -> Result<Texture2d, LoadingErr> {
+    let image = image::ImageReader::open(path)?.decode()?.to_rgba8();
+    let size = image.dimensions();
+    let data = image.into_vec();
+    let raw = glium::texture::RawImage2d::from_raw_rgba_reversed(&data, size);
+    Ok(Texture2d::new(display, raw)?)
+}
 #[derive(Debug)]
 pub enum LoadingErr {
     MeshError(tobj::LoadError),
@@ -296,7 +363,10 @@ pub enum LoadingErr {
     ShaderError(ProgramCreationError),
     ScriptError(ParseErr),
     FontLoadError(String),
-    InvalidScene(usize),
+    InvalidDialogueState(String),
+    MissingScene(String),
+    SaveError(ParseErr),
+    ImportError(ParseErr),
 }
 impl From<std::io::Error>       for LoadingErr { fn from(e: std::io::Error)       -> Self { Self::IoError(e)       } }
 impl From<tobj::LoadError>      for LoadingErr { fn from(e: tobj::LoadError)      -> Self { Self::MeshError(e)     } }
@@ -306,6 +376,22 @@ impl From<TextureCreationError> for LoadingErr { fn from(e: TextureCreationError
 impl From<ProgramCreationError> for LoadingErr { fn from(e: ProgramCreationError) -> Self { Self::ShaderError(e)   } }
 impl From<BufferCreationError>  for LoadingErr { fn from(e: BufferCreationError)  -> Self { Self::GpuMeshError(e)  } }
 impl From<&str> for LoadingErr { fn from(e: &str) -> Self { Self::FontLoadError(e.to_string()) } }
+impl std::fmt::Display for LoadingErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MeshError(e)     => write!(f, "couldn't load mesh, {e}"),
+            Self::IoError(e)       => write!(f, "{e}"),
+            Self::ImageError(e)    => write!(f, "couldn't load image, {e}"),
+            Self::GpuImageError(e) => write!(f, "couldn't send image to the gpu, {e}"),
+            Self::GpuMeshError(e)  => write!(f, "couldn't send mesh to the gpu, {e}"),
+            Self::ShaderError(e)   => write!(f, "{e}"),
+            Self::FontLoadError(e) => write!(f, "couldn't load font, {e}"),
+            Self::InvalidDialogueState(path) => write!(f, "the saved dialogue no longer fits `{path}`"),
+            Self::MissingScene(scene) => write!(f, "scene `{scene}` doesn't exist"),
+            Self::ScriptError(e) | Self::SaveError(e) | Self::ImportError(e) => write!(f, "{e}"),
+        }
+    }
+}
 pub struct Mesh {
     pub indices:  IndexBuffer<u32>,
     pub vertices: VertexBuffer<Vertex>,
@@ -317,6 +403,19 @@ impl Mesh {
         path: impl AsRef<Path> + 'a,
         display: &Display
     ) -> Result<Vec<Mesh>, LoadingErr> {
+        if let Some((file, name)) = path.as_ref().to_str().and_then(gltf_import::split_mesh_path) {
+            let meshes = gltf_import::read_mesh(file, name).map_err(LoadingErr::ImportError)?;
+            let mut results = Vec::new();
+            for gltf_import::MeshData { positions, uvs, normals, indices } in meshes {
+                let vertices: Vec<Vertex> = positions.into_iter().map(|[x, y, z]| Vertex::new(x, y, z)).collect();
+                let uvs: Vec<TextureCoords> = uvs.into_iter().map(|[u, v]| TextureCoords::new(u, v)).collect();
+                let normals: Vec<Normal> = normals.into_iter().map(|[x, y, z]| Normal::new(x, y, z)).collect();
+
+                let (indices, vertices, uvs, normals) = mesh!(display, &indices, &vertices, &uvs, &normals);
+                results.push(Mesh { vertices, uvs, normals, indices });
+            }
+            return Ok(results)
+        }
         let (meshes, _) = tobj::load_obj(path.as_ref(), &tobj::GPU_LOAD_OPTIONS)?;
         let mut results = Vec::new();
         for tobj::Model { mesh, .. } in meshes {
@@ -0,0 +1,202 @@
+use std::{path::{Path, PathBuf}, collections::{HashMap, HashSet}, sync::mpsc::{channel, Receiver}};
+use thin_engine::{prelude::*, Display, text_renderer::TextDrawError};
+use notify::{Watcher, RecommendedWatcher, RecursiveMode, Event};
+use crate::{screens, graphics::{GraphicsData, LoadingErr, SHADERS_PATH}, file_types::{*, scenes::GameScenes, script::Script}};
+
+/// how long a file has to go without changing before it's loaded again, editors often save in a few steps
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+/// the most lines of errors shown at once
+const MAX_LINES: usize = 18;
+
+/// watches the files the game has loaded and loads them again when they change.
+/// a file that doesn't load keeps its old version and what went wrong is shown on screen.
+/// items and enemies are loaded again along with everything that refers to them,
+/// but stats are only noted as changed since the player's stats are laid out by them
+pub struct HotReload {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// files are watched through their directory, since editors often replace a file rather than write to it
+    dirs: HashSet<PathBuf>,
+    /// asset paths, as they're written, whose directory is already watched
+    seen: HashSet<String>,
+    /// files that have changed and when they last did
+    pending: HashMap<PathBuf, Instant>,
+    /// what went wrong loading each file that didn't, along with the file
+    errors: Vec<(PathBuf, String)>,
+}
+impl HotReload {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        Ok(Self {
+            watcher: notify::recommended_watcher(sender)?, events,
+            dirs: HashSet::new(), seen: HashSet::new(), pending: HashMap::new(), errors: Vec::new(),
+        })
+    }
+    /// watches the directory a file is in
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        match path.as_ref().parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => self.watch_dir(dir),
+            _ => self.watch_dir("."),
+        }
+    }
+    pub fn watch_dir(&mut self, dir: impl AsRef<Path>) {
+        let Ok(dir) = dir.as_ref().canonicalize() else { return };
+        if self.dirs.contains(&dir) { return }
+        if self.watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok() { self.dirs.insert(dir); }
+    }
+    /// watches the scene files read from `scenes_path` by [`GameScenes::load_tracked`],
+    /// along with the directory itself if it is one so new scene files are noticed
+    pub fn watch_scenes(&mut self, scenes_path: &str, read: &[PathBuf]) {
+        if Path::new(scenes_path).is_dir() { self.watch_dir(scenes_path) }
+        for file in read { self.watch(file) }
+        for catalogue in [stats::STATS_PATH, items::ITEMS_PATH, enemies::ENEMIES_PATH] { self.watch(catalogue) }
+        self.watch_dir(SHADERS_PATH);
+    }
+    /// files that changed and have since settled, given as full paths
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        for event in self.events.try_iter().flatten() {
+            if !(event.kind.is_modify() || event.kind.is_create()) { continue }
+            for path in event.paths { self.pending.insert(path, Instant::now()); }
+        }
+        let mut settled: Vec<PathBuf> = self.pending.iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &settled { self.pending.remove(path); }
+        settled.sort();
+        settled
+    }
+    /// loads whatever changed again. scenes are all loaded again from `scenes_path`,
+    /// keeping the current scene by its name, and images, meshes, scripts and shaders are replaced in `graphics`
+    pub fn update(
+        &mut self, scenes_path: &str, scenes: &mut GameScenes, current_scene: &mut usize,
+        graphics: &mut GraphicsData, display: &Display
+    ) {
+        let assets = graphics.images.keys().chain(graphics.meshes.keys()).chain(graphics.scripts.keys());
+        let unseen: Vec<String> = assets.filter(|k| !self.seen.contains(*k)).cloned().collect();
+        for asset in unseen {
+            self.watch(gltf_import::split_mesh_path(&asset).map_or(asset.as_str(), |(file, _)| file));
+            self.seen.insert(asset);
+        }
+        let changed = self.changed();
+        if changed.is_empty() { return }
+        let shaders = Path::new(SHADERS_PATH).canonicalize().ok();
+        let is = |path: &Path, other: &str| Path::new(other).canonicalize().is_ok_and(|p| p == path);
+        let mut reload_scenes = false;
+        for path in changed {
+            let name = path.display().to_string();
+            // a glTF file can be both scenes and meshes
+            if is_scene_file(&path) { reload_scenes = true }
+            if is(&path, stats::STATS_PATH) {
+                let note = format!("note: {} changed, restart the game to use the new stats\n", stats::STATS_PATH);
+                self.record(PathBuf::from(stats::STATS_PATH), Err(note));
+            } else if let Some(catalogue) = [items::ITEMS_PATH, enemies::ENEMIES_PATH].into_iter().find(|c| is(&path, c)) {
+                let result = self.reload_catalogue(catalogue, scenes_path, scenes, current_scene, graphics, display);
+                self.record(PathBuf::from(catalogue), result);
+            } else if path.extension().is_some_and(|e| e == "glsl") && path.parent() == shaders.as_deref() {
+                let result = graphics.reload_shaders(display).map_err(|e| describe(e, &name));
+                self.record(PathBuf::from(SHADERS_PATH), result);
+            } else {
+                let result = graphics.reload_file(&path, display).map_err(|e| describe(e, &name));
+                self.record(path, result);
+            }
+        }
+        if reload_scenes {
+            let result = self.reload_scenes(scenes_path, scenes, current_scene, graphics, display);
+            self.record(PathBuf::from(scenes_path), result);
+        }
+    }
+    fn reload_scenes(
+        &mut self, scenes_path: &str, scenes: &mut GameScenes, current_scene: &mut usize,
+        graphics: &mut GraphicsData, display: &Display
+    ) -> Result<(), String> {
+        let (new_scenes, index) = self.load_scenes(scenes_path, &scenes[*current_scene].name)?;
+        graphics.load_scene(&new_scenes[index], display).map_err(|e| describe(e, scenes_path))?;
+        (*scenes, *current_scene) = (new_scenes, index);
+        Ok(())
+    }
+    /// the scenes in `scenes_path` and where the scene named `current` is in them
+    fn load_scenes(&mut self, scenes_path: &str, current: &str) -> Result<(GameScenes, usize), String> {
+        let mut read = Vec::new();
+        let result = GameScenes::load_tracked(scenes_path, &mut read);
+        self.watch_scenes(scenes_path, &read);
+        let new_scenes = result.map_err(|e| report(&e, scenes_path))?;
+        let index = new_scenes.find(current)
+            .ok_or_else(|| format!("error: scene `{current}` is no longer in {scenes_path}\n"))?;
+        Ok((new_scenes, index))
+    }
+    /// loads the items or enemies again, then the scenes and scripts checked against them.
+    /// if any of those don't load the old catalogues are put back
+    fn reload_catalogue(
+        &mut self, catalogue: &str, scenes_path: &str, scenes: &mut GameScenes, current_scene: &mut usize,
+        graphics: &mut GraphicsData, display: &Display
+    ) -> Result<(), String> {
+        let (old_items, old_enemies) = (items::item_catalogue(), enemies::enemy_catalogue());
+        let loaded = if catalogue == items::ITEMS_PATH { items::load_items(catalogue) } else { enemies::load_enemies(catalogue) };
+        let result = loaded.map_err(|e| report(&e, catalogue)).and_then(|_| {
+            let scripts = graphics.scripts.keys()
+                .map(|path| Script::from_file(path).map(|s| (path.clone(), s)).map_err(|e| report(&e, path)))
+                .collect::<Result<Vec<_>, String>>()?;
+            let (new_scenes, index) = self.load_scenes(scenes_path, &scenes[*current_scene].name)?;
+            graphics.load_scene(&new_scenes[index], display).map_err(|e| describe(e, scenes_path))?;
+            graphics.scripts.extend(scripts);
+            (*scenes, *current_scene) = (new_scenes, index);
+            Ok(())
+        });
+        if result.is_err() {
+            items::set_item_catalogue((*old_items).clone());
+            enemies::set_enemy_catalogue((*old_enemies).clone());
+        }
+        result
+    }
+    /// keeps what went wrong with a file until it loads
+    fn record(&mut self, path: PathBuf, result: Result<(), String>) {
+        self.errors.retain(|(p, _)| *p != path);
+        if let Err(e) = result { self.errors.push((path, e)) }
+    }
+    /// draws what went wrong with every file that didn't load, over everything else
+    pub fn draw_errors(
+        &self, graphics: &mut GraphicsData, frame: &mut impl Surface, display: &Display
+    ) -> Result<(), TextDrawError> {
+        let lines = self.errors.iter().flat_map(|(_, e)| e.lines()).filter(|l| !l.trim().is_empty());
+        for (i, line) in lines.take(MAX_LINES).enumerate() {
+            graphics.draw_text(frame, display, line, vec3(1.0, 0.35, 0.35), screens::line_pos(i))?;
+        }
+        Ok(())
+    }
+}
+fn is_scene_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "scn" || e == "ron" || e == "json") || gltf_import::is_gltf(path)
+}
+/// a parse error in the way the compiler-like reports are written everywhere else
+fn report(e: &ParseErr, path: &str) -> String {
+    let source = if gltf_import::is_gltf(path) { String::new() } else { std::fs::read_to_string(path).unwrap_or_default() };
+    e.report(path, &source)
+}
+fn describe(e: LoadingErr, path: &str) -> String {
+    match e {
+        LoadingErr::ScriptError(e) | LoadingErr::ImportError(e) => report(&e, path),
+        e => format!("error: {e}\n --> {path}\n"),
+    }
+}
+
+#[test]
+fn changed_files_are_noticed() {
+    let dir = std::env::temp_dir().join(format!("sword-ward-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("room.scn");
+    std::fs::write(&path, "#room[]").unwrap();
+    let mut hot_reload = HotReload::new().unwrap();
+    hot_reload.watch_scenes(&dir.display().to_string(), std::slice::from_ref(&path));
+    assert!(hot_reload.changed().is_empty());
+
+    std::fs::write(&path, "#room[camera[]]").unwrap();
+    let start = Instant::now();
+    let mut changed = Vec::new();
+    while changed.is_empty() && start.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(SETTLE_TIME);
+        changed = hot_reload.changed();
+    }
+    assert_eq!(changed, vec![path.canonicalize().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
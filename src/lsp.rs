@@ -0,0 +1,521 @@
+use std::{collections::HashMap, error::Error, ops::Range, path::{Path, PathBuf}, str::FromStr};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{self as lsp, notification::Notification as _};
+use thin_engine::prelude::*;
+use crate::{
+    commands::files_with,
+    file_types::{*, scenes::{GameScenes, Object, ObjectType, Reward}, script::{Script, Segment, CONTROLS}}
+};
+
+/// everything a scene can contain and how it's written
+const OBJECTS: &[(&str, &str)] = &[
+    ("trigger",     "trigger[cube | cylinder | sphere]"),
+    ("collider",    "collider[cube | cylinder | sphere]"),
+    ("image",       "image[image path]"),
+    ("mesh",        "mesh[obj path, image path]"),
+    ("exit",        "exit#name[scene, exit, trigger]"),
+    ("dialogue",    "dialogue[script path, trigger, auto, priority[n], prompt[text]]"),
+    ("pickup",      "pickup#name[item amount | tag, objects..]"),
+    ("encounter",   "encounter[enemy, trigger]"),
+    ("if",          "if[requirements, object]"),
+    ("point_light", "point_light[strength, colour[r, g, b]]"),
+    ("dir_light",   "dir_light[strength, colour[r, g, b]]"),
+    ("use",         "use#name[prefab, param[value]..]"),
+];
+const TRANSFORM: &[(&str, &str)] = &[
+    ("pos",   "pos[x, y, z]"),
+    ("rot",   "rot[x, y, z] in degrees"),
+    ("scale", "scale[x, y, z] or scale[n]"),
+];
+/// objects that contain other objects
+const CONTAINERS: &[&str] = &["", "exit", "dialogue", "pickup", "encounter", "if"];
+
+/// `lsp` runs a language server for scenes and scripts, talking over stdin and stdout.
+/// gives the exit code
+pub fn run() -> i32 {
+    // a catalogue that doesn't load just means its names aren't known, the server still runs
+    let catalogues = [
+        (stats::STATS_PATH, stats::load_stat_schema(stats::STATS_PATH)),
+        (items::ITEMS_PATH, items::load_items(items::ITEMS_PATH)),
+        (enemies::ENEMIES_PATH, enemies::load_enemies(enemies::ENEMIES_PATH)),
+    ];
+    for (path, result) in catalogues {
+        if let Err(e) = result { eprint!("{}", e.report(path, &std::fs::read_to_string(path).unwrap_or_default())) }
+    }
+    let (connection, io_threads) = Connection::stdio();
+    let result = serve(connection).and_then(|_| Ok(io_threads.join()?));
+    match result {
+        Ok(()) => 0,
+        Err(e) => { eprintln!("language server stopped, {e}"); 1 },
+    }
+}
+fn serve(connection: Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let capabilities = lsp::ServerCapabilities {
+        text_document_sync: Some(lsp::TextDocumentSyncCapability::Options(lsp::TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(lsp::TextDocumentSyncKind::FULL),
+            // saved files are read again for when they aren't open
+            save: Some(lsp::TextDocumentSyncSaveOptions::Supported(true)),
+            ..Default::default()
+        })),
+        completion_provider: Some(lsp::CompletionOptions {
+            trigger_characters: Some(vec!["[".to_string(), "$".to_string()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(lsp::OneOf::Left(true)),
+        hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    let mut workspace = Workspace::load();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? { return Ok(()) }
+                connection.sender.send(Message::Response(workspace.respond(request)))?;
+            },
+            Message::Notification(notification) => if let Some(uri) = workspace.update(notification) {
+                let diagnostics = workspace.open.get(&uri).map(|d| d.diagnostics.clone()).unwrap_or_default();
+                let params = lsp::PublishDiagnosticsParams { uri, diagnostics, version: None };
+                let method = lsp::notification::PublishDiagnostics::METHOD.to_string();
+                connection.sender.send(Message::Notification(Notification::new(method, params)))?;
+            },
+            Message::Response(_) => (),
+        }
+    }
+    Ok(())
+}
+/// a scene or script file along with what's found in it, worked out once each time it changes
+struct Document {
+    source: String,
+    calls: Vec<Call>,
+    /// tags it gives or checks for
+    tags: Vec<String>,
+    diagnostics: Vec<lsp::Diagnostic>,
+}
+impl Document {
+    fn new(uri: &lsp::Uri, source: String) -> Self {
+        let mut tags = Vec::new();
+        let err = if is(uri, "scn") { source.parse::<GameScenes>().map(|scenes| scene_tags(&scenes, &mut tags)).err() }
+            else if is(uri, "scr") { source.parse::<Script>().map(|script| script_tags(&script, &mut tags)).err() }
+            else { None };
+        let diagnostics = err.map(|e| diagnostics(uri, &source, &e)).unwrap_or_default();
+        Self { calls: calls(&source), source, tags, diagnostics }
+    }
+    /// the file at `uri` as it is on disk
+    fn read(uri: &lsp::Uri) -> Option<Self> {
+        Some(Self::new(uri, std::fs::read_to_string(uri_path(uri)).ok()?))
+    }
+}
+/// the scenes and scripts being worked on. open files are kept as the editor has them rather than as they are on disk
+struct Workspace {
+    open: HashMap<lsp::Uri, Document>,
+    /// the files in the current directory as they are on disk, read again when they're saved
+    disk: HashMap<lsp::Uri, Document>,
+}
+impl Workspace {
+    fn load() -> Self {
+        let disk = files_with(&["scn", "scr"]).into_iter().filter_map(|path| {
+            let uri = path_uri(&path.canonicalize().ok()?)?;
+            Some((uri.clone(), Document::read(&uri)?))
+        });
+        Self { open: HashMap::new(), disk: disk.collect() }
+    }
+    /// keeps up with the editor, giving the document that changed
+    fn update(&mut self, notification: Notification) -> Option<lsp::Uri> {
+        use lsp::notification::*;
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp::DidOpenTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                self.open.insert(uri.clone(), Document::new(&uri, params.text_document.text));
+                Some(uri)
+            },
+            DidChangeTextDocument::METHOD => {
+                let params: lsp::DidChangeTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                // the whole document is sent each time
+                let text = params.content_changes.into_iter().last()?.text;
+                let uri = params.text_document.uri;
+                self.open.insert(uri.clone(), Document::new(&uri, text));
+                Some(uri)
+            },
+            DidSaveTextDocument::METHOD => {
+                let params: lsp::DidSaveTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                if let Some(document) = Document::read(&uri) { self.disk.insert(uri, document); }
+                None
+            },
+            DidCloseTextDocument::METHOD => {
+                let params: lsp::DidCloseTextDocumentParams = serde_json::from_value(notification.params).ok()?;
+                self.open.remove(&params.text_document.uri);
+                Some(params.text_document.uri)
+            },
+            _ => None,
+        }
+    }
+    fn respond(&self, request: Request) -> Response {
+        use lsp::request::*;
+        match request.method.as_str() {
+            Completion::METHOD => handle::<Completion>(request, |p| {
+                let (uri, document, offset) = self.at(&p.text_document_position)?;
+                Some(lsp::CompletionResponse::Array(self.completion(uri, document, offset)))
+            }),
+            GotoDefinition::METHOD => handle::<GotoDefinition>(request, |p| {
+                let (_, document, offset) = self.at(&p.text_document_position_params)?;
+                self.definition(document, offset).map(lsp::GotoDefinitionResponse::Scalar)
+            }),
+            HoverRequest::METHOD => handle::<HoverRequest>(request, |p| {
+                let (_, document, offset) = self.at(&p.text_document_position_params)?;
+                let (value, span) = hover(document, offset)?;
+                Some(lsp::Hover {
+                    contents: lsp::HoverContents::Markup(lsp::MarkupContent { kind: lsp::MarkupKind::Markdown, value }),
+                    range: Some(range(&document.source, span)),
+                })
+            }),
+            method => Response::new_err(request.id, ErrorCode::MethodNotFound as i32, format!("unsupported request `{method}`")),
+        }
+    }
+    fn at<'a>(&'a self, p: &'a lsp::TextDocumentPositionParams) -> Option<(&'a lsp::Uri, &'a Document, usize)> {
+        let document = self.open.get(&p.text_document.uri)?;
+        Some((&p.text_document.uri, document, offset(&document.source, p.position)))
+    }
+    /// every open file with `extension` followed by the ones in the current directory that aren't open
+    fn sources<'a>(&'a self, extension: &'a str) -> impl Iterator<Item = (&'a lsp::Uri, &'a Document)> {
+        let open = self.open.iter().filter(move |(uri, _)| is(uri, extension));
+        let disk = self.disk.iter().filter(move |(uri, _)| is(uri, extension) && !self.open.contains_key(*uri));
+        open.chain(disk)
+    }
+    /// tags given by scripts, checked for by scenes and scripts and given by pickups and encounters
+    fn known_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.sources("scr").chain(self.sources("scn"))
+            .flat_map(|(_, document)| document.tags.iter().cloned())
+            .collect();
+        for enemy in enemies::enemy_catalogue().iter() {
+            tags.extend([enemy.won_tag.clone(), enemy.lost_tag.clone(), enemy.escaped_tag.clone()]);
+        }
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+    /// what could be written at `offset`, going by the call the cursor is in
+    fn completion(&self, uri: &lsp::Uri, document: &Document, offset: usize) -> Vec<lsp::CompletionItem> {
+        let (source, calls) = (document.source.as_str(), &document.calls);
+        let call = calls.iter().rev().find(|c| c.open < offset && c.contains(offset));
+        let name = call.map(|c| c.name(source));
+        let item = |label: &str, detail: &str, kind| lsp::CompletionItem {
+            label: label.to_string(), detail: Some(detail.to_string()), kind: Some(kind), ..Default::default()
+        };
+        let objects = || OBJECTS.iter().chain(TRANSFORM).map(|(l, d)| item(l, d, lsp::CompletionItemKind::FUNCTION));
+        let stats = || (0..stats::stat_schema().len())
+            .map(|i| item(&stats::stat_schema().get(i).name, "stat", lsp::CompletionItemKind::VARIABLE));
+        let tags = || self.known_tags().into_iter().map(|t| item(&t, "tag", lsp::CompletionItemKind::CONSTANT));
+        let requirements = || stats().chain(tags())
+            .chain(["has", "count", "affinity"].map(|l| item(l, &format!("{l}[..]"), lsp::CompletionItemKind::FUNCTION)));
+
+        if is(uri, "scn") {
+            let Some(call) = call else {
+                return [("include", "include[path]"), ("prefab", "prefab#name[objects..]")]
+                    .map(|(l, d)| item(l, d, lsp::CompletionItemKind::FUNCTION)).into()
+            };
+            let arg = call.arg_index(offset);
+            match name.unwrap_or_default() {
+                _ if call.depth == 0 => std::iter::once(item("camera", "camera[pos, rot]", lsp::CompletionItemKind::FUNCTION))
+                    .chain(objects()).collect(),
+                "if" if arg == 0 => requirements().collect(),
+                "trigger" | "collider" if arg == 0 => ["cube", "cylinder", "sphere"]
+                    .map(|l| item(l, "collider type", lsp::CompletionItemKind::ENUM_MEMBER)).into(),
+                name if CONTAINERS.contains(&name) => objects().collect(),
+                name if OBJECTS.iter().any(|(o, _)| *o == name) => TRANSFORM.iter()
+                    .map(|(l, d)| item(l, d, lsp::CompletionItemKind::FUNCTION)).collect(),
+                _ => Vec::new(),
+            }
+        } else {
+            match name {
+                None | Some("$opt") => {
+                    let word = &source[..offset];
+                    let word = &word[word.rfind(|c: char| c.is_whitespace() || "[],".contains(c)).map(|i| i + 1).unwrap_or(0)..];
+                    if !word.starts_with('$') { return Vec::new() }
+                    CONTROLS.iter().map(|(c, _)| item(c, "control word", lsp::CompletionItemKind::KEYWORD)).collect()
+                },
+                Some("$req") => requirements().collect(),
+                Some("$add" | "$rem") => tags().collect(),
+                Some("$stat" | "$xp") => stats().collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+    /// from an exit to the scene it leads to when the cursor is on the scene,
+    /// otherwise to the exit it arrives at
+    fn definition(&self, document: &Document, offset: usize) -> Option<lsp::Location> {
+        let source = &document.source;
+        let exit = document.calls.iter().rev().find(|c| c.contains(offset) && c.name(source) == "exit")?;
+        let [scene, name, ..] = exit.args.as_slice() else { return None };
+        let arg = |span: &Range<usize>| lexer::unquote(&source[span.clone()]).unwrap_or_else(|| source[span.clone()].to_string());
+        let (scene, name) = (arg(scene), arg(name));
+        let on_scene = offset > exit.open && exit.arg_index(offset) == 0;
+        for (uri, target) in self.sources("scn") {
+            let (calls, target) = (&target.calls, &target.source);
+            let Some(found) = calls.iter().find(|c| c.depth == 0 && target[c.prefix.clone()] == format!("#{scene}")) else { continue };
+            let arrive = calls.iter().find(|c| {
+                found.span.start <= c.span.start && c.span.end <= found.span.end && target[c.prefix.clone()] == format!("exit#{name}")
+            });
+            let found = if on_scene { found } else { arrive.unwrap_or(found) };
+            return Some(lsp::Location::new(uri.clone(), range(target, found.prefix.clone())))
+        }
+        None
+    }
+}
+fn handle<R: lsp::request::Request>(request: Request, respond: impl FnOnce(R::Params) -> R::Result) -> Response {
+    match serde_json::from_value(request.params) {
+        Ok(params) => Response::new_ok(request.id, respond(params)),
+        Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+fn is(uri: &lsp::Uri, extension: &str) -> bool {
+    uri_path(uri).extension().is_some_and(|e| e == extension)
+}
+/// the file a uri points at. editors percent encode spaces and anything outside ascii
+fn uri_path(uri: &lsp::Uri) -> PathBuf {
+    PathBuf::from(uri.path().as_estr().decode().into_string_lossy().into_owned())
+}
+/// a `file://` uri for `path`, percent encoding whatever can't be written in one as is
+fn path_uri(path: &Path) -> Option<lsp::Uri> {
+    let mut uri = String::from("file://");
+    for b in path.to_str()?.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) { uri.push(b as char) }
+        else { uri.push_str(&format!("%{b:02X}")) }
+    }
+    lsp::Uri::from_str(&uri).ok()
+}
+/// tags given by pickups and checked for by `if`s
+fn scene_tags(scenes: &GameScenes, tags: &mut Vec<String>) {
+    for (o, _) in scenes.iter().flat_map(|scene| scene.all_objects()) {
+        match &o.object_type {
+            ObjectType::If { requirements, .. } => tags.extend(requirements.tags().into_iter().map(String::from)),
+            ObjectType::Pickup { reward: Reward::Tag(tag), .. } => tags.push(tag.clone()),
+            _ => (),
+        }
+    }
+}
+/// tags added, removed or checked for by a script
+fn script_tags(script: &Script, tags: &mut Vec<String>) {
+    fn segment_tags(segment: &Segment, tags: &mut Vec<String>) {
+        tags.extend(segment.add_tags.iter().chain(&segment.remove_tags).cloned());
+        if let Some(requirements) = &segment.requirements { tags.extend(requirements.tags().into_iter().map(String::from)) }
+        for option in &segment.options { segment_tags(option, tags) }
+    }
+    for segment in &script.segments { segment_tags(segment, tags) }
+}
+/// the parse errors in a scene or script file, pointing at where they are.
+/// errors in other files, like ones a scene includes, are left for those files
+fn diagnostics(uri: &lsp::Uri, source: &str, err: &ParseErr) -> Vec<lsp::Diagnostic> {
+    let this_file = |file: &str| Path::new(file).canonicalize().is_ok_and(|f| f == uri_path(uri));
+    err.locations().into_iter().filter(|l| l.file.is_none_or(this_file)).map(|Location { span, chain, err, .. }| lsp::Diagnostic {
+        range: range(source, span.unwrap_or(0..0)),
+        severity: Some(lsp::DiagnosticSeverity::ERROR),
+        source: Some("sword-ward".to_string()),
+        message: if chain.is_empty() { err.to_string() } else { format!("{err}\nin {}", chain.join(" > ")) },
+        ..Default::default()
+    }).collect()
+}
+/// where the object under the cursor ends up in its scene once the objects containing it are applied,
+/// along with the name of the object
+fn hover(document: &Document, offset: usize) -> Option<(String, Range<usize>)> {
+    let source = &document.source;
+    let objects: Vec<&Call> = document.calls.iter().filter(|c| c.depth > 0 && c.contains(offset)).filter(|c| {
+        let name = c.name(source);
+        name.is_empty() || OBJECTS.iter().any(|(o, _)| *o == name)
+    }).collect();
+    let innermost = objects.last()?;
+    let mut trans = Mat4::IDENTITY;
+    let mut parsed = Vec::new();
+    for call in &objects {
+        let object: Object = source[call.span.clone()].parse().ok()?;
+        trans *= object.trans;
+        parsed.push(object);
+    }
+    // adding 0 turns -0 into 0
+    let round = |v: Vec3| {
+        let [x, y, z] = [v.x, v.y, v.z].map(|n| (n * 1000.0).round() / 1000.0 + 0.0);
+        format!("{x}, {y}, {z}")
+    };
+    let (pos, rot, scale) = parsed.last()?.local_transform();
+    let mut value = format!("`{}`\n\nin the scene at {}\n\n", &source[innermost.prefix.clone()], round(trans.position()));
+    value += &format!("pos[{}], rot[{}], scale[{}]", round(pos), round(rot), round(scale));
+    if objects.len() > 1 {
+        let parents: Vec<String> = objects[..objects.len() - 1].iter().map(|c| format!("`{}`", &source[c.prefix.clone()])).collect();
+        value += &format!("\n\ninside {}", parents.join(" > "));
+    }
+    Some((value, innermost.prefix.clone()))
+}
+/// a `prefix[..]` in a file, found without parsing the file so it works while the file has mistakes in it
+#[derive(Debug)]
+struct Call {
+    /// the last word before the bracket, like `exit#door` or `$req`
+    prefix: Range<usize>,
+    /// where the bracket opens
+    open: usize,
+    /// from the prefix to just after the closing bracket, or to the end of the file if it's never closed
+    span: Range<usize>,
+    closed: bool,
+    /// each argument, trimmed
+    args: Vec<Range<usize>>,
+    separators: Vec<usize>,
+    /// how many calls it's inside of
+    depth: usize,
+}
+impl Call {
+    fn contains(&self, offset: usize) -> bool { self.span.start <= offset && (offset < self.span.end || !self.closed) }
+    /// the prefix without its name, `exit` for `exit#door`
+    fn name<'a>(&self, source: &'a str) -> &'a str { source[self.prefix.clone()].split('#').next().unwrap_or_default() }
+    fn arg_index(&self, offset: usize) -> usize { self.separators.iter().filter(|s| **s < offset).count() }
+}
+/// every call in `source`, each one before the calls inside it
+fn calls(source: &str) -> Vec<Call> {
+    let stripped = lexer::strip_comments(source);
+    let tokens = lexer::tokenize_lenient(&stripped);
+    let mut calls: Vec<Call> = Vec::new();
+    // the calls that are still open and where their current argument started
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut boundary = 0;
+    let end_arg = |call: &mut Call, start: usize, end: usize| {
+        let arg = &stripped[start..end];
+        let trimmed = arg.trim_start();
+        let start = start + arg.len() - trimmed.len();
+        if !trimmed.trim_end().is_empty() { call.args.push(start..start + trimmed.trim_end().len()) }
+    };
+    for token in tokens {
+        match token.kind {
+            lexer::TokenKind::Open => {
+                let before = stripped[boundary..token.span.start].trim_end();
+                let word = before.char_indices().rev().find(|(_, c)| c.is_whitespace())
+                    .map(|(i, c)| i + c.len_utf8()).unwrap_or(0);
+                let prefix = boundary + word..boundary + before.len();
+                calls.push(Call {
+                    open: token.span.start, span: prefix.start..stripped.len(), prefix, closed: false,
+                    args: Vec::new(), separators: Vec::new(), depth: open.len(),
+                });
+                open.push((calls.len() - 1, token.span.end));
+            },
+            lexer::TokenKind::Close => if let Some((call, start)) = open.pop() {
+                end_arg(&mut calls[call], start, token.span.start);
+                calls[call].span.end = token.span.end;
+                calls[call].closed = true;
+            },
+            lexer::TokenKind::Separator => if let Some((call, start)) = open.last_mut() {
+                end_arg(&mut calls[*call], *start, token.span.start);
+                calls[*call].separators.push(token.span.start);
+                *start = token.span.end;
+            },
+            _ => continue,
+        }
+        boundary = token.span.end;
+    }
+    for (call, start) in open { end_arg(&mut calls[call], start, stripped.len()) }
+    calls
+}
+/// lsp positions count lines and then utf-16 code units
+fn position(source: &str, offset: usize) -> lsp::Position {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) { offset -= 1 }
+    let line_start = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    lsp::Position::new(source[..offset].matches('\n').count() as u32, source[line_start..offset].encode_utf16().count() as u32)
+}
+fn range(source: &str, span: Range<usize>) -> lsp::Range {
+    lsp::Range::new(position(source, span.start), position(source, span.end))
+}
+fn offset(source: &str, position: lsp::Position) -> usize {
+    let line_start: usize = source.split_inclusive('\n').take(position.line as usize).map(str::len).sum();
+    let mut units = 0;
+    for (i, c) in source[line_start..].char_indices() {
+        if units >= position.character || c == '\n' { return line_start + i }
+        units += c.len_utf16() as u32;
+    }
+    source.len()
+}
+
+#[test]
+fn language_server_answers_requests() {
+    use serde_json::{json, Value};
+    let (server, client) = Connection::memory();
+    let server = std::thread::spawn(move || serve(server).map_err(|e| e.to_string()));
+    let request = |id: i32, method: &str, params: Value| -> Value {
+        client.sender.send(Message::Request(Request::new(id.into(), method.to_string(), params))).unwrap();
+        loop {
+            if let Message::Response(response) = client.receiver.recv().unwrap() { return response.result.unwrap() }
+        }
+    };
+    let notify = |method: &str, params: Value| {
+        client.sender.send(Message::Notification(Notification::new(method.to_string(), params))).unwrap();
+    };
+    let diagnostics = || loop {
+        if let Message::Notification(n) = client.receiver.recv().unwrap() { return n.params["diagnostics"].clone() }
+    };
+    request(0, "initialize", json!({ "capabilities": {} }));
+    notify("initialized", json!({}));
+
+    let uri = "file:///lsp_test.scn";
+    let source = "#a[\n    camera[]\n    #group[pos[1, 0, 0], exit#door[b, door, trigger[cube, pos[0, 2, 0]]]]\n]\n\
+        #b[\n    camera[]\n    exit#door[a, door, trigger[cub]]\n]";
+    notify("textDocument/didOpen", json!({ "textDocument": { "uri": uri, "languageId": "scn", "version": 0, "text": source } }));
+    let errors = diagnostics();
+    assert_eq!(errors.as_array().unwrap().len(), 1);
+    assert_eq!(errors[0]["range"]["start"], json!({ "line": 6, "character": 31 }));
+
+    let source = source.replace("[cub]", "[cube]");
+    notify("textDocument/didChange", json!({
+        "textDocument": { "uri": uri, "version": 1 }, "contentChanges": [{ "text": source }]
+    }));
+    assert_eq!(diagnostics(), json!([]));
+
+    let at = |line: u32, character: u32| json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+    // from the exit to the exit it arrives at, and from its scene to the scene
+    let definition = request(1, "textDocument/definition", at(2, 29));
+    assert_eq!(definition["range"]["start"], json!({ "line": 6, "character": 4 }));
+    let definition = request(2, "textDocument/definition", at(2, 35));
+    assert_eq!(definition["range"]["start"], json!({ "line": 4, "character": 0 }));
+
+    let hover = request(3, "textDocument/hover", at(2, 56));
+    let hover = hover["contents"]["value"].as_str().unwrap();
+    assert!(hover.contains("in the scene at 1, 2, 0"), "{hover}");
+
+    let completion = request(4, "textDocument/completion", at(2, 13));
+    let labels: Vec<&str> = completion.as_array().unwrap().iter().map(|i| i["label"].as_str().unwrap()).collect();
+    assert!(labels.contains(&"point_light") && labels.contains(&"scale"));
+
+    // tags from other open files are kept up to date as they change
+    let greeting = "file:///lsp_greeting.scr";
+    notify("textDocument/didOpen", json!({ "textDocument": { "uri": greeting, "languageId": "scr", "version": 0, "text": "hi $add[met]" } }));
+    diagnostics();
+    let farewell = "file:///lsp_farewell.scr";
+    notify("textDocument/didOpen", json!({ "textDocument": { "uri": farewell, "languageId": "scr", "version": 0, "text": "bye $rem[]" } }));
+    diagnostics();
+    let tags = |id: i32| {
+        let at = json!({ "textDocument": { "uri": farewell }, "position": { "line": 0, "character": 9 } });
+        let completion = request(id, "textDocument/completion", at);
+        completion.as_array().unwrap().iter().map(|i| i["label"].as_str().unwrap().to_string()).collect::<Vec<_>>()
+    };
+    assert!(tags(5).contains(&"met".to_string()));
+    notify("textDocument/didChange", json!({
+        "textDocument": { "uri": greeting, "version": 1 }, "contentChanges": [{ "text": "hi $add[greeted]" }]
+    }));
+    diagnostics();
+    let labels = tags(6);
+    assert!(labels.contains(&"greeted".to_string()) && !labels.contains(&"met".to_string()));
+
+    // saved files are read from disk, even where their path has to be percent encoded
+    let dir = std::env::temp_dir().join(format!("sword ward lsp {}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("café notes.scr");
+    std::fs::write(&path, "hi $add[wrote]").unwrap();
+    let notes = path_uri(&path).unwrap();
+    assert!(notes.as_str().contains("sword%20ward") && notes.as_str().contains("caf%C3%A9%20notes.scr"));
+    assert_eq!(uri_path(&notes), path);
+    notify("textDocument/didSave", json!({ "textDocument": { "uri": notes.as_str() } }));
+    assert!(tags(7).contains(&"wrote".to_string()));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    request(8, "shutdown", Value::Null);
+    notify("exit", Value::Null);
+    server.join().unwrap().unwrap();
+}
@@ -1,11 +1,16 @@
 use thin_engine::{text_renderer::*, prelude::*};
 use glium::{texture::DepthTexture2d, draw_parameters::*, uniforms::MagnifySamplerFilter};
-use std::{cell::{Cell, RefCell}, rc::Rc};
+use std::{cell::RefCell, rc::Rc, collections::HashMap, f32::consts::TAU};
 
 mod graphics;
 mod file_types;
 mod collision;
-use file_types::{scenes::*, *};
+mod screens;
+mod encounter;
+mod commands;
+mod lsp;
+mod hot_reload;
+use file_types::{scenes::*, save::*, migration::*, *};
 use graphics::*;
 use collision::*;
 
@@ -14,33 +19,185 @@ pub const INT_SCALE: u32 = 125;
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 enum Action {
     DialougeUp, DialougeDown, DialougeSelect,
-    PlayerUp, PlayerDown, PlayerLeft, PlayerRight, PlayerInteract
+    PlayerUp, PlayerDown, PlayerLeft, PlayerRight, PlayerInteract,
+    SaveGame, LoadGame, NextSlot,
+    MenuUp, MenuDown, MenuLeft, MenuRight, MenuSelect,
+    ToggleInventory, ToggleJournal,
 }
 use Action::*;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct PlayerData {
-    recovery: u8,
-    fitness:  u8,
-    charisma: u8,
+    pub name: String,
+    /// the player's own value for each stat in the [`StatSchema`](stats::StatSchema),
+    /// combined with the stats above it to get its actual value
+    stats: Vec<i32>,
+    /// experience towards each stat's next level, see [`StatDef::thresholds`](stats::StatDef::thresholds)
+    xp: Vec<u32>,
     acquired_tags: Vec<String>,
     read_scripts:  Vec<String>,
+    /// item ids from the [`ItemCatalogue`](items::ItemCatalogue) and how many the player has
+    inventory: Vec<(String, u32)>,
+    /// keys of the pickups the player has collected, see [`ObjectType::Pickup`]
+    picked_up: Vec<String>,
+    /// the stage each started quest is on, see [`QuestLog`](quests::QuestLog)
+    quests: Vec<(String, usize)>,
+    /// how much each NPC, named by its object in the scene, likes the player
+    affinity: Vec<(String, i32)>,
     pub pos: Vec3,
+    pub facing: Vec3,
+}
+impl Default for PlayerData {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            stats: stats::stat_schema().start_values(),
+            xp: vec![0; stats::stat_schema().len()],
+            acquired_tags: Vec::new(), read_scripts: Vec::new(), inventory: Vec::new(),
+            picked_up: Vec::new(), quests: Vec::new(), affinity: Vec::new(),
+            pos: Vec3::ZERO, facing: vec3(0.0, 0.0, 1.0),
+        }
+    }
 }
 impl PlayerData {
     pub fn collider(&self) -> (ColliderType, Mat4) { (ColliderType::Cylinder, Mat4::from_pos(self.pos)) }
-    pub fn recovery(&self)   -> u8 { self.recovery }
-    pub fn focus(&self)      -> u8 { self.recovery }
-    pub fn reasoning(&self)  -> u8 { self.recovery }
-    pub fn fitness(&self)    -> u8 { self.fitness  }
-    pub fn speed(&self)      -> u8 { self.fitness  }
-    pub fn strength(&self)   -> u8 { self.fitness  }
-    pub fn charisma(&self)   -> u8 { self.charisma }
-    pub fn expression(&self) -> u8 { self.charisma }
-    pub fn deception(&self)  -> u8 { self.charisma }
+    pub fn stat(&self, stat: usize) -> f32 { stats::stat_schema().evaluate(stat, &self.stats) }
+    pub fn change_stat(&mut self, stat: usize, amount: i32) { self.stats[stat] += amount }
+    /// raises the stat for every threshold the experience passes, giving how many it passed
+    pub fn gain_xp(&mut self, stat: usize, amount: u32) -> i32 {
+        let schema = stats::stat_schema();
+        let before = schema.levels(stat, self.xp[stat]);
+        self.xp[stat] += amount;
+        let levels = schema.levels(stat, self.xp[stat]) - before;
+        self.change_stat(stat, levels);
+        levels
+    }
+    pub fn level_up_message(&self, stat: usize) -> String {
+        let schema = stats::stat_schema();
+        let name = &schema.get(stat).name;
+        let mut chars = name.chars();
+        let name = chars.next().map(|c| c.to_uppercase().collect::<String>() + chars.as_str()).unwrap_or_default();
+        format!("{name} went up to {}", self.stat(stat))
+    }
+    pub fn inventory(&self) -> &[(String, u32)] { &self.inventory }
+    pub fn item_count(&self, item: &str) -> u32 {
+        self.inventory.iter().find(|(i, _)| i == item).map(|(_, n)| *n).unwrap_or(0)
+    }
+    /// items that aren't stackable are capped at one
+    pub fn give_item(&mut self, item: &str, amount: u32) {
+        let stackable = items::item_catalogue().find(item).is_none_or(|i| i.stackable);
+        match self.inventory.iter_mut().find(|(i, _)| i == item) {
+            Some((_, n)) => *n += amount,
+            None => self.inventory.push((item.to_string(), amount)),
+        }
+        if !stackable { for (i, n) in &mut self.inventory { if i == item { *n = (*n).min(1) } } }
+    }
+    pub fn affinity(&self, npc: &str) -> i32 {
+        self.affinity.iter().find(|(n, _)| n == npc).map(|(_, v)| *v).unwrap_or(0)
+    }
+    pub fn change_affinity(&mut self, npc: &str, amount: i32) {
+        match self.affinity.iter_mut().find(|(n, _)| n == npc) {
+            Some((_, v)) => *v += amount,
+            None => self.affinity.push((npc.to_string(), amount)),
+        }
+    }
+    pub fn quest_stage(&self, quest: &str) -> Option<usize> {
+        self.quests.iter().find(|(q, _)| q == quest).map(|(_, stage)| *stage)
+    }
+    pub fn set_quest_stage(&mut self, quest: &str, stage: usize) {
+        match self.quests.iter_mut().find(|(q, _)| q == quest) {
+            Some((_, s)) => *s = stage,
+            None => self.quests.push((quest.to_string(), stage)),
+        }
+    }
+    /// gives the player a pickup's reward and removes it from the scene for good
+    pub fn collect(&mut self, pickup: &Object) {
+        let ObjectType::Pickup { reward, key, .. } = &pickup.object_type else { return };
+        match reward {
+            Reward::Item(item, amount) => self.give_item(item, *amount),
+            Reward::Tag(tag) => if !self.acquired_tags.contains(tag) { self.acquired_tags.push(tag.clone()) },
+        }
+        self.picked_up.push(key.clone());
+    }
+    pub fn take_item(&mut self, item: &str, amount: u32) {
+        for (i, n) in &mut self.inventory { if i == item { *n = n.saturating_sub(amount) } }
+        self.inventory.retain(|(_, n)| *n > 0);
+    }
+}
+/// picks the dialogue the player would start by interacting. higher priority
+/// dialogue wins, then whichever is closest to being in front of the player
+fn interact_target<'a>(scene: &'a Scene, player: &PlayerData) -> Option<(&'a Object, &'a str, Mat4)> {
+    let (p_col_type, p_col_trans) = player.collider();
+    let score = |t: &Mat4| {
+        let offset = vec4(0.0, 0.0, 0.0, 1.0).transform(t).truncate() - player.pos;
+        let facing = if offset.length() > 0.0 { player.facing.dot(offset.normalise()) } else { 1.0 };
+        offset.length() * (2.0 - facing)
+    };
+    let priority = |o: &Object| match o.object_type { ObjectType::Dialogue { priority, .. } => priority, _ => 0.0 };
+    scene.dialogue(player).into_iter().filter(|(d, _, t)| {
+        let ObjectType::Dialogue { script_path, collider, .. } = &d.object_type else { return false };
+        !player.read_scripts.contains(script_path) && collider.triggers(player).into_iter()
+            .any(|(c, ct)| p_col_type.is_inside_of(p_col_trans, c, *t*ct))
+    }).min_by(|(a, _, at), (b, _, bt)| {
+        priority(b).total_cmp(&priority(a)).then(score(at).total_cmp(&score(bt)))
+    })
+}
+fn save_game(
+    slot: SaveSlot, scenes: &GameScenes, migrations: &Migrations,
+    current_scene: usize, player: &PlayerData, dialogue: &script::ScriptReader
+) -> Result<(), std::io::Error> {
+    SaveData {
+        version: SAVE_VERSION,
+        content_version: migrations.latest(),
+        scene: scenes[current_scene].name.clone(),
+        player: player.clone(),
+        dialogue: dialogue.state(),
+        unknown_stats: Vec::new(),
+    }.write_to_slot(slot)
+}
+/// prints every error in a data file the way a compiler would and quits
+fn or_exit<T>(path: &str, result: Result<T, ParseErr>) -> T {
+    result.unwrap_or_else(|e| {
+        let source = std::fs::read_to_string(path).unwrap_or_default();
+        eprint!("{}", e.report(path, &source));
+        eprintln!("could not load {path}, {} error(s)", e.count());
+        std::process::exit(1)
+    })
+}
+/// loads a save and the scene it was made in, giving the new scene index, player and dialogue
+/// along with anything the save refers to that no longer exists
+fn load_game(
+    slot: SaveSlot, scenes: &GameScenes, migrations: &Migrations, origins: &[creation::Origin],
+    graphics: &mut GraphicsData, display: &thin_engine::Display
+) -> Result<(usize, PlayerData, script::ScriptReader, Vec<String>), LoadingErr> {
+    let mut save = SaveData::from_file(slot.path()).map_err(LoadingErr::SaveError)?;
+    migrations.apply(&mut save);
+    let missing = missing_references(&save, scenes, origins);
+    let scene = scenes.find(&save.scene).ok_or_else(|| LoadingErr::MissingScene(save.scene.clone()))?;
+    graphics.load_scene(&scenes[scene], display)?;
+    let mut dialogue = script::ScriptReader::new();
+    if let Some(state) = &save.dialogue { dialogue.restore(state, graphics)? }
+    Ok((scene, save.player, dialogue, missing))
+}
+fn interact_prompt(dialogue: &Object, owner: &str) -> String {
+    if let ObjectType::Dialogue { prompt: Some(prompt), .. } = &dialogue.object_type { return prompt.clone() }
+    let mut chars = owner.chars();
+    match chars.next() {
+        Some(first) => format!("Talk to {}{}", first.to_uppercase(), chars.as_str().replace('_', " ")),
+        None => "Talk".to_string(),
+    }
 }
 
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("fmt")     => std::process::exit(commands::fmt(&args[1..])),
+        Some("check")   => std::process::exit(commands::check(&args[1..])),
+        Some("convert") => std::process::exit(commands::convert(&args[1..])),
+        Some("lsp")     => std::process::exit(lsp::run()),
+        _ => (),
+    }
     let input = { use base_input_codes::*; input_map!(
         (DialougeUp,     KeyW, KeyK, ArrowUp),
         (DialougeDown,   KeyS, KeyJ, ArrowDown),
@@ -49,21 +206,59 @@ fn main() {
         (PlayerRight, KeyD),
         (PlayerUp,    KeyW),
         (PlayerDown,  KeyS),
-        (PlayerInteract, Enter, Space)
+        (PlayerInteract, Enter, Space),
+        (SaveGame, F5),
+        (NextSlot, F6),
+        (LoadGame, F9),
+        (MenuUp,     ArrowUp),
+        (MenuDown,   ArrowDown),
+        (MenuLeft,   ArrowLeft),
+        (MenuRight,  ArrowRight),
+        (MenuSelect, Enter),
+        (ToggleInventory, KeyI, Tab),
+        (ToggleJournal, KeyL)
     ) };
     let mut dialogue = script::ScriptReader::new();
     let mut opt_selection: f32 = 0.0; // proccesed into a usize
+    let mut auto_dialogue_inside  = Vec::<String>::new();
+    let mut auto_dialogue_started = HashMap::<String, Instant>::new();
+    let mut save_slot = SaveSlot::Manual(1);
+    let mut notifications = Vec::<(String, Instant)>::new();
+    // achievement titles and icons, shown for longer than notifications
+    let mut toasts = Vec::<(String, Option<String>, Instant)>::new();
 
-    let scenes = std::fs::read_to_string("test.scn").unwrap();
-    let scenes: GameScenes = scenes.parse().unwrap();
+    or_exit(stats::STATS_PATH, stats::load_stat_schema(stats::STATS_PATH));
+    or_exit(items::ITEMS_PATH, items::load_items(items::ITEMS_PATH));
+    or_exit(enemies::ENEMIES_PATH, enemies::load_enemies(enemies::ENEMIES_PATH));
+    // a scene file or a directory of them can be given to play instead
+    let scenes_path = args.first().map(String::as_str).unwrap_or(SCENES_PATH);
+    let mut scene_files = Vec::new();
+    let scenes = RefCell::new(or_exit(scenes_path, GameScenes::load_tracked(scenes_path, &mut scene_files)));
+    let mut hot_reload = match hot_reload::HotReload::new() {
+        Ok(mut hot_reload) => { hot_reload.watch_scenes(scenes_path, &scene_files); Some(hot_reload) },
+        Err(e) => { eprintln!("files won't be reloaded when they change, {e}"); None },
+    };
     let mut current_scene = 0;
+    let migrations = or_exit(MIGRATIONS_PATH, Migrations::from_file(MIGRATIONS_PATH));
+    let quest_log = or_exit(quests::QUESTS_PATH, quests::QuestLog::from_file(quests::QUESTS_PATH));
+    let achievements = or_exit(achievements::ACHIEVEMENTS_PATH, achievements::Achievements::from_file(achievements::ACHIEVEMENTS_PATH));
+    let mut profile = or_exit(&achievements::Profile::path().display().to_string(), achievements::Profile::load());
 
     let mut delta_time = Duration::ZERO;
     let mut player_gravity = 0.0;
     let player_collider = ColliderType::Cylinder;
-    let mut player = PlayerData {
-        recovery: 1, fitness: 1, charisma: 1, acquired_tags: Vec::new(), read_scripts: Vec::new(), pos: Vec3::ZERO
-    };
+    let mut player = PlayerData::default();
+    let creation_config = or_exit(creation::CREATION_PATH, creation::CreationConfig::from_file(creation::CREATION_PATH));
+    // kept so loaded saves can tell which tags an origin gives
+    let origins = creation_config.origins.clone();
+    let mut creation = Some(screens::CharacterCreation::new(creation_config));
+    let mut inventory: Option<screens::Inventory> = None;
+    let mut journal = false;
+    let mut current_encounter: Option<encounter::Encounter> = None;
+    // enemies whose encounter trigger the player is in, so encounters only start on entering
+    let mut encounter_inside = Vec::<String>::new();
+    // text typed this frame, for entering the player's name
+    let typed_text = RefCell::new(String::new());
 
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
@@ -76,7 +271,7 @@ fn main() {
         frame_col.borrow_mut().replace(Texture2d::empty(     display, 4*INT_SCALE, 3*INT_SCALE).unwrap());
         frame_dep.borrow_mut().replace(DepthTexture2d::empty(display, 4*INT_SCALE, 3*INT_SCALE).unwrap());
         let mut new_graphics = GraphicsData::new(display).unwrap();
-        new_graphics.load_scene(&scenes[0], display).unwrap();
+        new_graphics.load_scene(&scenes.borrow()[0], display).unwrap();
         graphics.replace(Some(new_graphics));
     })
     .with_update(|input, display, _s, _t, window| {
@@ -84,6 +279,38 @@ fn main() {
         let frame_start = Instant::now();
         let mut graphics = graphics.borrow_mut();
         let graphics = graphics.as_mut().unwrap();
+        let mut scenes = scenes.borrow_mut();
+        if let Some(hot_reload) = &mut hot_reload {
+            hot_reload.update(scenes_path, &mut scenes, &mut current_scene, graphics, display);
+        }
+
+        if input.pressed(NextSlot) {
+            save_slot = save_slot.next();
+            notifications.push((format!("{save_slot} selected"), Instant::now()));
+        }
+        if input.pressed(SaveGame) && creation.is_none() && current_encounter.is_none() {
+            let message = match save_game(save_slot, &scenes, &migrations, current_scene, &player, &dialogue) {
+                Ok(()) => format!("Saved to {save_slot}"),
+                Err(e) => format!("Couldn't save to {save_slot}: {e}"),
+            };
+            notifications.push((message, Instant::now()));
+        }
+        if input.pressed(LoadGame) {
+            let message = match load_game(save_slot, &scenes, &migrations, &origins, graphics, display) {
+                Ok((scene, data, reader, missing)) => {
+                    (current_scene, player, dialogue) = (scene, data, reader);
+                    for m in missing { notifications.push((format!("Save is out of date: {m}"), Instant::now())) }
+                    player_gravity = 0.0;
+                    auto_dialogue_inside.clear();
+                    encounter_inside.clear();
+                    current_encounter = None;
+                    creation = None;
+                    format!("Loaded {save_slot}")
+                },
+                Err(e) => format!("Couldn't load {save_slot}: {e}"),
+            };
+            notifications.push((message, Instant::now()));
+        }
 
         use crate::glium::framebuffer::SimpleFrameBuffer;
         let frame_col = frame_col.borrow();
@@ -94,7 +321,43 @@ fn main() {
         frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 0.0);
         let view = Mat4::view_matrix_2d((4, 3));
         
-        graphics.draw_scene(&mut frame, &scenes[current_scene], display, &player).unwrap();
+        let typed = typed_text.take();
+        if let Some(screen) = &mut creation {
+            if let Some(new_player) = screen.update(input, &typed) {
+                player = new_player;
+                creation = None;
+            } else {
+                screen.draw(graphics, &mut frame, display).unwrap();
+            }
+        }
+        if creation.is_none() {
+            graphics.draw_scene(&mut frame, &scenes[current_scene], display, &player).unwrap();
+        }
+        if let Some(screen) = &mut current_encounter {
+            if screen.update(input, &player) {
+                for stat in screen.finish(&mut player) {
+                    notifications.push((player.level_up_message(stat), Instant::now()))
+                }
+                for message in quest_log.update(&mut player) { notifications.push((message, Instant::now())) }
+                current_encounter = None;
+            } else {
+                screen.draw(graphics, &mut frame, display).unwrap();
+            }
+        }
+        let in_menu = creation.is_some() || current_encounter.is_some() || dialogue.current_segment().is_some();
+        if input.pressed(ToggleInventory) && !in_menu {
+            inventory = match inventory { Some(_) => None, None => Some(screens::Inventory::default()) };
+            journal = false;
+        }
+        if let Some(screen) = &mut inventory {
+            screen.update(input, &player);
+            screen.draw(graphics, &mut frame, display, &player).unwrap();
+        }
+        if input.pressed(ToggleJournal) && !in_menu {
+            journal = !journal;
+            inventory = None;
+        }
+        if journal && inventory.is_none() { screens::draw_journal(graphics, &mut frame, display, &quest_log, &player).unwrap() }
         
         let text_renderer = TextRenderer {
             shader:      &graphics.text_shader,
@@ -105,8 +368,9 @@ fn main() {
             display
         };
 
+        let mut prompt = None;
         // render dialogue
-        if let Some(segment) = &dialogue.current_segment() {
+        if let (Some(segment), None) = (&dialogue.current_segment(), &current_encounter) {
             text_renderer.draw(
                 &segment.text, Vec3::ONE, &mut frame,
                 Mat4::from_pos_and_scale(
@@ -145,14 +409,24 @@ fn main() {
             if input.pressed(DialougeSelect) {
                 opt_selection = 0.0;
                 dialogue.next(selection, &mut player);
+                for stat in dialogue.take_level_ups() {
+                    notifications.push((player.level_up_message(stat), Instant::now()))
+                }
+                for message in quest_log.update(&mut player) { notifications.push((message, Instant::now())) }
+                if let Some(enemy) = dialogue.take_encounter() {
+                    current_encounter = Some(encounter::Encounter::new(
+                        enemies::enemy_catalogue().find(&enemy).unwrap().clone(), &player
+                    ));
+                }
             }
-        } else {
+        } else if creation.is_none() && inventory.is_none() && !journal && current_encounter.is_none() {
             // no dialogue being read
             player_gravity += 9.8*delta_time.as_secs_f32();
             let dir = input.dir_max_len_1(PlayerRight, PlayerLeft, PlayerUp, PlayerDown);
-            player.pos += vec3(dir.x, 0.0, dir.y)
-                .scale(delta_time.as_secs_f32())
-                .transform(&Quat::from_y_rot(scenes[current_scene].cam_rot.y).into());
+            let movement = vec3(dir.x, 0.0, dir.y)
+                .transform(&Quat::from_y_rot(scenes[current_scene].cam_rot.y * TAU/360.0).into());
+            if movement.length() > 0.0 { player.facing = movement.normalise() }
+            player.pos += movement.scale(delta_time.as_secs_f32());
             player.pos.y -= player_gravity * delta_time.as_secs_f32();
             let (p_col_type, p_col_trans) = player.collider();
             for (c, t) in &scenes[current_scene].colliders(&player) {
@@ -174,6 +448,9 @@ fn main() {
                         for (e, t) in &scenes[current_scene].exits(&player) {
                             if &e.name == exit_name {
                                 player.pos = vec4(0.0, 0.0, 0.0, 1.0).transform(t).truncate();
+                                if let Err(e) = save_game(SaveSlot::Auto, &scenes, &migrations, current_scene, &player, &dialogue) {
+                                    notifications.push((format!("Couldn't autosave: {e}"), Instant::now()));
+                                }
                                 break 'load_scene;
                             }
                         }
@@ -182,17 +459,108 @@ fn main() {
                 }
             }
 
-            // try start dialogue
-            if input.pressed(PlayerInteract) {
-                for (d, t) in &scenes[current_scene].dialogue(&player) {
-                    let Object { object_type: Dialogue { script_path, collider }, .. } = d else { unreachable!() };
-                    for (c, ct) in collider.triggers(&player) {
-                        if p_col_type.is_inside_of(p_col_trans, c, *t*ct) {
-                            dialogue.set_script(script_path, &mut player, graphics)
+            // try start an encounter, only when first entering its trigger
+            let mut now_inside = Vec::new();
+            for (e, t) in &scenes[current_scene].encounters(&player) {
+                let Object { object_type: Encounter { enemy, collider }, .. } = e else { unreachable!() };
+                let inside = collider.triggers(&player).into_iter()
+                    .any(|(c, ct)| p_col_type.is_inside_of(p_col_trans, c, *t*ct));
+                if !inside { continue }
+                now_inside.push(enemy.clone());
+                if encounter_inside.contains(enemy) || current_encounter.is_some() { continue }
+                let enemy = enemies::enemy_catalogue().find(enemy).unwrap().clone();
+                current_encounter = Some(encounter::Encounter::new(enemy, &player));
+            }
+            encounter_inside = now_inside;
+
+            // try start automatic dialogue, only when first entering its trigger
+            let mut now_inside = Vec::new();
+            for (d, owner, t) in &scenes[current_scene].dialogue(&player) {
+                let Object { object_type: Dialogue { script_path, collider, auto: Some(auto), .. }, .. } = d
+                else { continue };
+                let inside = collider.triggers(&player).into_iter()
+                    .any(|(c, ct)| p_col_type.is_inside_of(p_col_trans, c, *t*ct));
+                if !inside { continue }
+                now_inside.push(script_path.clone());
+                if auto_dialogue_inside.contains(script_path) || dialogue.current_segment().is_some() { continue }
+                match auto {
+                    AutoStart::Once => dialogue.set_script(script_path, owner, &mut player, graphics),
+                    AutoStart::Cooldown(secs) => {
+                        let ready = auto_dialogue_started.get(script_path)
+                            .is_none_or(|i| i.elapsed().as_secs_f32() >= *secs);
+                        if ready {
+                            auto_dialogue_started.insert(script_path.clone(), Instant::now());
+                            dialogue.replay_script(script_path, owner, &mut player, graphics)
                         }
+                    },
+                }
+            }
+            auto_dialogue_inside = now_inside;
+
+            // try pick something up
+            let pickup = scenes[current_scene].pickups(&player).into_iter().find(|(o, t)| {
+                let Object { object_type: Pickup { objects, .. }, .. } = o else { return false };
+                objects.iter().flat_map(|o| o.triggers(&player))
+                    .any(|(c, ct)| p_col_type.is_inside_of(p_col_trans, c, *t*ct))
+            });
+            if let Some((o, _)) = pickup {
+                let ObjectType::Pickup { reward, .. } = &o.object_type else { unreachable!() };
+                if input.pressed(PlayerInteract) {
+                    player.collect(o);
+                    for message in quest_log.update(&mut player) { notifications.push((message, Instant::now())) }
+                    if let Reward::Item(item, amount) = reward {
+                        let catalogue = items::item_catalogue();
+                        let name = catalogue.find(item).map(|i| i.name.as_str()).unwrap_or(item);
+                        let message = if *amount > 1 { format!("Picked up {name} x{amount}") }
+                            else { format!("Picked up {name}") };
+                        notifications.push((message, Instant::now()));
                     }
-                } 
+                } else {
+                    prompt = Some(match reward {
+                        Reward::Item(item, _) => format!("Pick up {}", items::item_catalogue().find(item)
+                            .map(|i| i.name.as_str()).unwrap_or(item)),
+                        Reward::Tag(_) => "Pick up".to_string(),
+                    });
+                }
             }
+            // try start dialogue
+            else if let Some((d, owner, _)) = interact_target(&scenes[current_scene], &player) {
+                let Object { object_type: Dialogue { script_path, .. }, .. } = d else { unreachable!() };
+                if input.pressed(PlayerInteract) { dialogue.set_script(script_path, owner, &mut player, graphics) }
+                else if dialogue.current_segment().is_none() { prompt = Some(interact_prompt(d, owner)) }
+            }
+        }
+        if let Some(prompt) = prompt {
+            let pos = vec2(0.1-(width as f32/height as f32), -0.85);
+            graphics.draw_text(&mut frame, display, &prompt, Vec3::splat(0.9), pos).unwrap();
+        }
+        if creation.is_none() {
+            let unlocked = achievements.check(&mut profile, &player);
+            for a in &unlocked { toasts.push((a.title.clone(), a.icon.clone(), Instant::now())) }
+            if !unlocked.is_empty() {
+                if let Err(e) = profile.write() {
+                    notifications.push((format!("Couldn't save achievements: {e}"), Instant::now()))
+                }
+            }
+        }
+        toasts.retain(|(.., start)| start.elapsed() < Duration::from_secs(5));
+        for (i, (title, icon, _)) in toasts.iter().enumerate() {
+            let pos = vec2(0.4, 0.9 - i as f32 * 0.2);
+            if let Some(icon) = icon { graphics.draw_image(&mut frame, display, icon, pos + vec2(-0.1, -0.05), 0.07).unwrap() }
+            graphics.draw_text(&mut frame, display, "Achievement unlocked", Vec3::splat(0.6), pos).unwrap();
+            graphics.draw_text(&mut frame, display, title, Vec3::splat(0.9), pos - vec2(0.0, 0.1)).unwrap();
+        }
+        if debug_affinity() {
+            for (i, (npc, value)) in player.affinity.iter().enumerate() {
+                let pos = vec2(0.1, 0.9 - i as f32 / 10.0);
+                graphics.draw_text(&mut frame, display, &format!("{npc}: {value}"), Vec3::splat(0.75), pos).unwrap();
+            }
+        }
+        if let Some(hot_reload) = &hot_reload { hot_reload.draw_errors(graphics, &mut frame, display).unwrap() }
+        notifications.retain(|(_, start)| start.elapsed() < Duration::from_secs(3));
+        for (i, (message, _)) in notifications.iter().enumerate() {
+            let pos = vec2(0.1, -0.85 + i as f32 / 10.0);
+            graphics.draw_text(&mut frame, display, message, Vec3::splat(0.75), pos).unwrap();
         }
         
         let size = window.inner_size().into();
@@ -214,6 +582,14 @@ fn main() {
 
         frame.finish().unwrap();
         delta_time = frame_start.elapsed().min(Duration::from_millis(100));
+    })
+    .with_event_handler(|event, _e, _w| {
+        use thin_engine::winit::event::{Event, WindowEvent};
+        if let Event::WindowEvent { event: WindowEvent::KeyboardInput { event, .. }, .. } = event {
+            if event.state.is_pressed() {
+                if let Some(text) = event.text { typed_text.borrow_mut().push_str(&text) }
+            }
+        }
     })
         .build(event_loop)
         .unwrap();
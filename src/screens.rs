@@ -0,0 +1,167 @@
+use thin_engine::{prelude::*, Display, text_renderer::TextDrawError};
+use crate::{
+    Action::{self, *}, PlayerData, graphics::{GraphicsData, LoadDrawError},
+    file_types::{stats, items, creation::CreationConfig, quests::{QuestLog, QuestState}}
+};
+
+pub(crate) const SELECTED:   f32 = 0.9;
+pub(crate) const UNSELECTED: f32 = 0.6;
+/// where the first line of a menu goes, lines go downwards from here
+pub(crate) fn line_pos(line: usize) -> Vec2 { vec2(0.1 - 4.0/3.0, 0.9 - line as f32 / 10.0) }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CreationRow { Name, Origin, Stat(usize), Begin }
+/// lets the player name themselves, pick an origin and spend points on their stats
+pub struct CharacterCreation {
+    config: CreationConfig,
+    name: String,
+    origin: usize,
+    /// points spent on each stat in the schema
+    spent: Vec<i32>,
+    row: usize,
+}
+impl CharacterCreation {
+    pub fn new(config: CreationConfig) -> Self {
+        let spent = vec![0; stats::stat_schema().len()];
+        Self { config, name: String::new(), origin: 0, spent, row: 0 }
+    }
+    fn rows(&self) -> Vec<CreationRow> {
+        let mut rows = vec![CreationRow::Name];
+        if !self.config.origins.is_empty() { rows.push(CreationRow::Origin) }
+        rows.extend((0..self.spent.len()).map(CreationRow::Stat));
+        rows.push(CreationRow::Begin);
+        rows
+    }
+    fn remaining(&self) -> i32 { self.config.budget - self.spent.iter().sum::<i32>() }
+    /// handles input for the frame, giving the new player once they choose to begin
+    pub fn update(&mut self, input: &InputMap<Action>, typed: &str) -> Option<PlayerData> {
+        let rows = self.rows();
+        if input.pressed(MenuDown) { self.row = (self.row + 1) % rows.len() }
+        if input.pressed(MenuUp)   { self.row = (self.row + rows.len() - 1) % rows.len() }
+        let change = input.pressed(MenuRight) as i32 - input.pressed(MenuLeft) as i32;
+        match rows[self.row] {
+            CreationRow::Name => for c in typed.chars() {
+                if c == '\u{8}' { self.name.pop(); }
+                else if c.is_alphanumeric() || c == ' ' || c == '-' || c == '\'' { self.name.push(c) }
+            },
+            CreationRow::Origin => {
+                let len = self.config.origins.len() as i32;
+                self.origin = (self.origin as i32 + change).rem_euclid(len) as usize;
+            },
+            CreationRow::Stat(stat) => {
+                let spent = self.spent[stat] + change;
+                if (0..=self.config.max_per_stat).contains(&spent) && self.remaining() - change >= 0 {
+                    self.spent[stat] = spent;
+                }
+            },
+            CreationRow::Begin => if input.pressed(MenuSelect) && !self.name.trim().is_empty() {
+                let mut player = PlayerData { name: self.name.trim().to_string(), ..PlayerData::default() };
+                for (stat, spent) in self.spent.iter().enumerate() { player.change_stat(stat, *spent) }
+                if let Some(origin) = self.config.origins.get(self.origin) {
+                    player.acquired_tags.extend(origin.tags.iter().cloned());
+                }
+                return Some(player)
+            },
+        }
+        None
+    }
+    pub fn draw(
+        &self, graphics: &mut GraphicsData, frame: &mut impl Surface, display: &Display
+    ) -> Result<(), TextDrawError> {
+        let schema = stats::stat_schema();
+        let start = schema.start_values();
+        let own: Vec<i32> = start.iter().zip(&self.spent).map(|(a, b)| a + b).collect();
+        let mut draw = |text: &str, brightness: f32, line: usize| {
+            graphics.draw_text(frame, display, text, Vec3::splat(brightness), line_pos(line))
+        };
+        let mut line = 0;
+        for (i, row) in self.rows().into_iter().enumerate() {
+            let brightness = if i == self.row { SELECTED } else { UNSELECTED };
+            match row {
+                CreationRow::Name => draw(&format!("Name: {}_", self.name), brightness, line)?,
+                CreationRow::Origin => {
+                    let origin = &self.config.origins[self.origin];
+                    draw(&format!("Origin: < {} >", origin.name), brightness, line)?;
+                    line += 1;
+                    draw(&origin.description, 0.5, line)?;
+                },
+                CreationRow::Stat(stat) => {
+                    if stat == 0 {
+                        draw(&format!("Points left: {}", self.remaining()), 0.75, line)?;
+                        line += 1;
+                    }
+                    let indent = "  ".repeat(schema.depth(stat));
+                    let value = schema.evaluate(stat, &own);
+                    draw(&format!("{indent}{}: < {value} >", schema.get(stat).name), brightness, line)?;
+                },
+                CreationRow::Begin => draw("Begin", brightness, line)?,
+            }
+            line += 1;
+        }
+        Ok(())
+    }
+}
+/// lists what the player is carrying, with the selected item's description underneath
+#[derive(Debug, Default)]
+pub struct Inventory {
+    selected: usize,
+}
+impl Inventory {
+    pub fn update(&mut self, input: &InputMap<Action>, player: &PlayerData) {
+        let len = player.inventory().len().max(1);
+        if input.pressed(MenuDown) { self.selected = (self.selected + 1) % len }
+        if input.pressed(MenuUp)   { self.selected = (self.selected + len - 1) % len }
+        self.selected = self.selected.min(len - 1);
+    }
+    pub fn draw(
+        &self, graphics: &mut GraphicsData, frame: &mut impl Surface, display: &Display, player: &PlayerData
+    ) -> Result<(), LoadDrawError> {
+        graphics.draw_text(frame, display, "Inventory", Vec3::splat(0.75), line_pos(0))?;
+        if player.inventory().is_empty() {
+            graphics.draw_text(frame, display, "Nothing", Vec3::splat(UNSELECTED), line_pos(1))?;
+            return Ok(())
+        }
+        let catalogue = items::item_catalogue();
+        for (i, (id, count)) in player.inventory().iter().enumerate() {
+            let item = catalogue.find(id);
+            let name = item.map(|i| i.name.as_str()).unwrap_or(id);
+            let text = if *count > 1 { format!("{name} x{count}") } else { name.to_string() };
+            let brightness = if i == self.selected { SELECTED } else { UNSELECTED };
+            let pos = line_pos(i + 1);
+            if let Some(icon) = item.and_then(|i| i.icon.as_ref()) {
+                graphics.draw_image(frame, display, icon, pos + vec2(0.05, -0.05), 0.04)?;
+            }
+            graphics.draw_text(frame, display, &text, Vec3::splat(brightness), pos + vec2(0.15, 0.0))?;
+        }
+        let (id, _) = &player.inventory()[self.selected];
+        if let Some(item) = catalogue.find(id) {
+            graphics.draw_text(frame, display, &item.description, Vec3::splat(0.5), vec2(0.1 - 4.0/3.0, -0.75))?;
+        }
+        Ok(())
+    }
+}
+/// lists active quests with their stages so far, then the quests that are complete
+pub fn draw_journal(
+    graphics: &mut GraphicsData, frame: &mut impl Surface, display: &Display, log: &QuestLog, player: &PlayerData
+) -> Result<(), TextDrawError> {
+    let mut draw = |text: &str, brightness: f32, line: usize| {
+        graphics.draw_text(frame, display, text, Vec3::splat(brightness), line_pos(line))
+    };
+    draw("Journal", 0.75, 0)?;
+    let mut line = 1;
+    for quest in &log.quests {
+        let Some(QuestState::Stage(stage)) = quest.state(player) else { continue };
+        draw(&quest.title, SELECTED, line)?;
+        line += 1;
+        for (i, s) in quest.stages[..=stage].iter().enumerate() {
+            draw(&format!("  {}", s.description), if i == stage { UNSELECTED } else { 0.4 }, line)?;
+            line += 1;
+        }
+    }
+    for quest in &log.quests {
+        if quest.state(player) != Some(QuestState::Complete) { continue }
+        draw(&format!("{} (complete)", quest.title), 0.4, line)?;
+        line += 1;
+    }
+    Ok(())
+}